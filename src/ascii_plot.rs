@@ -0,0 +1,199 @@
+//! ascii_plot module contains the AsciiPlotter struct, which implements Plottable by drawing
+//! onto a grid of characters that is printed to the terminal by finalize().  It is a rough,
+//! headless preview for checking the geometry of a plot, e.g. over SSH, before sending it to hardware.
+//!
+
+use std::env;
+
+use crate::plottable::Plottable;
+
+const DEFAULT_COLUMNS: usize = 80; // Grid size when the terminal size isn't known.
+const DEFAULT_ROWS: usize = 24;
+const CHAR_ASPECT: f64 = 2.0;      // Height / width of a terminal character cell.
+
+pub struct AsciiPlotter {
+    min_x_mm: f64, // Plot rectangle, in mm.
+    min_y_mm: f64,
+    max_x_mm: f64,
+    max_y_mm: f64,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    cell_w_mm: f64, // Size of the area of the plot covered by one character.
+    cell_h_mm: f64,
+    ink: char,      // Character for cells the pen has drawn over.
+    blank: char,    // Character for the rest.
+    grid: Vec<Vec<char>>, // Rows of characters, top row first.
+}
+
+impl AsciiPlotter {
+    /// Create an AsciiPlotter for the plot rectangle with lower left corner (llx_mm, lly_mm)
+    /// and upper right corner (urx_mm, ury_mm), sized to fit the terminal.  The terminal size is
+    /// read from the COLUMNS and LINES environment variables, or taken as 80 x 24 if they aren't set.
+    /// One row is left free for the prompt.
+    pub fn new(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> AsciiPlotter {
+        let columns = env_size("COLUMNS").unwrap_or(DEFAULT_COLUMNS);
+        let rows = env_size("LINES").unwrap_or(DEFAULT_ROWS);
+        AsciiPlotter::with_size(llx_mm, lly_mm, urx_mm, ury_mm, columns, rows.saturating_sub(1).max(1))
+    }
+
+    /// Create an AsciiPlotter whose grid is at most columns x rows characters.  The plot rectangle
+    /// is scaled to fit, keeping its proportions, so one dimension of the grid may be smaller.
+    pub fn with_size(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64, columns: usize, rows: usize) -> AsciiPlotter {
+        // Check that the upper right is greater than the lower left.
+        if (urx_mm - llx_mm <= 0.0) || (ury_mm - lly_mm <= 0.0) {
+            panic!("Error: upper right is not greater than lower left.");
+        }
+        if columns == 0 || rows == 0 {
+            panic!("Parameters `columns` and `rows` must be greater than zero.")
+        }
+
+        // Whichever dimension fills the grid first sets the scale.
+        let cell_w_mm = ((urx_mm - llx_mm) / columns as f64).max((ury_mm - lly_mm) / (rows as f64 * CHAR_ASPECT));
+        let cell_h_mm = cell_w_mm * CHAR_ASPECT;
+        let used_columns = (((urx_mm - llx_mm) / cell_w_mm).ceil() as usize).max(1).min(columns);
+        let used_rows = (((ury_mm - lly_mm) / cell_h_mm).ceil() as usize).max(1).min(rows);
+
+        AsciiPlotter {
+            min_x_mm: llx_mm,
+            min_y_mm: lly_mm,
+            max_x_mm: urx_mm,
+            max_y_mm: ury_mm,
+            pos_x_mm: llx_mm,
+            pos_y_mm: lly_mm,
+            cell_w_mm,
+            cell_h_mm,
+            ink: '#',
+            blank: ' ',
+            grid: vec![vec![' '; used_columns]; used_rows],
+        }
+    }
+
+    /// Set the characters used for drawn cells (ink) and empty ones (blank).  Default '#' and ' '.
+    /// Call before drawing; cells already drawn keep their characters.
+    pub fn set_chars(&mut self, ink: char, blank: char) {
+        for cell in self.grid.iter_mut().flatten() {
+            if *cell == self.blank {
+                *cell = blank;
+            }
+        }
+        self.ink = ink;
+        self.blank = blank;
+    }
+
+    /// The grid as text, one line per row, top row first.
+    pub fn render(&self) -> String {
+        self.grid.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Convert (x_mm, y_mm) to a (column, row) cell, which may lie outside the grid.
+    fn mm2cell(&self, x_mm: f64, y_mm: f64) -> (i64, i64) {
+        (((x_mm - self.min_x_mm) / self.cell_w_mm).floor() as i64,
+         ((self.max_y_mm - y_mm) / self.cell_h_mm).floor() as i64)
+    }
+
+    /// Mark a cell as drawn, if it is on the grid.
+    fn plot_cell(&mut self, col: i64, row: i64) {
+        if row >= 0 && col >= 0 {
+            let ink = self.ink;
+            if let Some(cell) = self.grid.get_mut(row as usize).and_then(|r| r.get_mut(col as usize)) {
+                *cell = ink;
+            }
+        }
+    }
+
+    /// Mark the cells along the line between two cells, using Bresenham's algorithm.
+    fn plot_line(&mut self, from: (i64, i64), to: (i64, i64)) {
+        let (mut x, mut y) = from;
+        let (dx, dy) = ((to.0 - x).abs(), -(to.1 - y).abs());
+        let (sx, sy) = ((to.0 - x).signum(), (to.1 - y).signum());
+        let mut err = dx + dy;
+        loop {
+            self.plot_cell(x, y);
+            if (x, y) == to {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+}
+
+/// A positive size from an environment variable, if set.
+fn env_size(name: &str) -> Option<usize> {
+    env::var(name).ok()
+        .and_then(|s| s.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+}
+
+impl Plottable for AsciiPlotter {
+
+    /// Nothing to prepare; provided for compatibility.
+    fn initialize(&mut self) {}
+
+    /// Print the grid to the terminal.
+    fn finalize(&mut self) {
+        println!("{}", self.render());
+    }
+
+    /// Draw a straight line from present position to absolute position (destx_mm, desty_mm).
+    /// Parts of the line outside the plot rectangle are not shown.
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        let from = self.mm2cell(self.pos_x_mm, self.pos_y_mm);
+        let to = self.mm2cell(destx_mm, desty_mm);
+        self.plot_line(from, to);
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Move without drawing to absolute position (destx_mm, desty_mm).
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Draw from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Move without drawing from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Nothing to do; provided for compatibility.
+    fn pen_up(&mut self) {}
+
+    /// Mark the cell under the pen.
+    fn pen_down(&mut self) {
+        let (col, row) = self.mm2cell(self.pos_x_mm, self.pos_y_mm);
+        self.plot_cell(col, row);
+    }
+
+    /// Colors aren't shown; provided for compatibility.
+    fn change_color(&mut self, _color_name: &str) {}
+
+    /// The plot rectangle given when the plotter was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+}
@@ -0,0 +1,50 @@
+//! The attractor module contains code to plot strange attractors as fields of dots.
+//! http://paulbourke.net/fractals/peterdejong/
+//!
+
+use crate::plottable::Plottable;
+
+const MAX_DOTS: u32 = 20_000; // Even at a few dots per second, more than this takes hours.
+const SETTLE: u32 = 100;      // Iterations discarded before plotting, so the orbit reaches the attractor.
+
+/// Plot the Peter de Jong attractor as a cloud of dots:
+///     x' = sin(a*y) - cos(b*x)
+///     y' = sin(c*x) - cos(d*y)
+/// Both coordinates stay within [-2, 2].
+///
+/// plotter: device to plot to.
+/// a, b, c, d: map parameters.  Try (1.4, -2.3, 2.4, -2.1) or (-2.7, -0.09, -0.86, -2.2).
+/// iterations: number of dots to plot.  Capped at MAX_DOTS.
+/// scale_mm: mm per unit of the map, so the cloud spans at most 4 * scale_mm.
+///
+/// The pen is lifted between dots.  Attractors usually look best with hundreds of thousands
+/// of points, but every dot is a separate pen lift on the plotter, so the count is capped and
+/// the result is a sparser, stippled version of the on-screen image.  Dots that fall outside
+/// the plot bounds are skipped.
+///
+pub fn dejong_attractor(plotter: &mut impl Plottable, a: f64, b: f64, c: f64, d: f64, iterations: u32,
+                        scale_mm: f64, centerx_mm: f64, centery_mm: f64) {
+    let iterations = if iterations > MAX_DOTS {
+        println!("Limiting attractor to {} dots.", MAX_DOTS);
+        MAX_DOTS
+    } else {
+        iterations
+    };
+    let (llx_mm, lly_mm, urx_mm, ury_mm) = plotter.bounds();
+
+    let (mut x, mut y) = (0.0f64, 0.0f64);
+    for i in 0 .. (SETTLE + iterations) {
+        let next_x = (a * y).sin() - (b * x).cos();
+        let next_y = (c * x).sin() - (d * y).cos();
+        x = next_x;
+        y = next_y;
+        if i < SETTLE {
+            continue;
+        }
+        let px = centerx_mm + x * scale_mm;
+        let py = centery_mm + y * scale_mm;
+        if px >= llx_mm && px <= urx_mm && py >= lly_mm && py <= ury_mm {
+            plotter.dot(px, py);
+        }
+    }
+}
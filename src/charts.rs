@@ -0,0 +1,222 @@
+//! The charts module contains code to plot data as charts.
+//!
+
+use std::f64::consts::PI;
+use crate::fill;
+use crate::plottable::Plottable;
+use crate::shapes::{circle_at, sunburst};
+use crate::text::{self, Align};
+
+const RADAR_RINGS: u32 = 4; // Grid polygons in a radar chart, evenly spaced out to the full value.
+const BAR_FRACTION: f64 = 0.7;    // Share of each category's width taken by its bar; the rest is gap.
+const BAR_HATCH_MM: f64 = 0.8;    // Spacing of the lines filling a bar.
+const BAR_LABEL_MAX_MM: f64 = 5.0; // Tallest text used for bar labels.
+
+/// Plot values as a radar (spider) chart: one spoke per value, a polygon grid at quarters of max,
+/// and the closed data polygon with each vertex at radius_mm * value / max along its spoke.
+/// The first spoke points up and the rest follow clockwise.  Values are clamped to 0..max.
+///
+/// plotter: device to plot to.
+/// values: the data, at least 3 values.
+/// max: value that reaches the outer ring.
+/// cx_mm, cy_mm, radius_mm: center and size of the chart.
+/// labels: a name for each spoke, written just outside its end, or empty for no labels.
+///
+pub fn radar_chart(plotter: &mut impl Plottable, values: &[f64], max: f64, cx_mm: f64, cy_mm: f64,
+                   radius_mm: f64, labels: &[&str]) {
+    // Error checking.
+    if values.len() < 3 {
+        panic!("Parameter `values` must have at least 3 values.")
+    }
+    if max <= 0.0 {
+        panic!("Parameter `max` must be greater than zero.")
+    }
+    if !labels.is_empty() && labels.len() != values.len() {
+        panic!("Parameter `labels` must be empty or have one label per value.")
+    }
+    let n = values.len();
+    let angle = |i: usize| PI / 2.0 - 2.0 * PI * i as f64 / n as f64;
+    let point = |i: usize, r: f64| (cx_mm + r * angle(i).cos(), cy_mm + r * angle(i).sin());
+
+    // Spokes.
+    for i in 0 .. n {
+        let (x, y) = point(i, radius_mm);
+        plotter.move_to(cx_mm, cy_mm);
+        plotter.draw(x, y);
+    }
+    // Grid.
+    for ring in 1 .. (RADAR_RINGS + 1) {
+        let r = radius_mm * ring as f64 / RADAR_RINGS as f64;
+        draw_closed(plotter, (0 .. n).map(|i| point(i, r)).collect());
+    }
+    // Data.
+    draw_closed(plotter, (0 .. n).map(|i| point(i, radius_mm * values[i].max(0.0).min(max) / max)).collect());
+
+    // Labels, anchored so they sit outside the chart on whichever side their spoke ends.
+    let height_mm = radius_mm / 12.0;
+    for (i, label) in labels.iter().enumerate() {
+        let (x, y) = point(i, radius_mm + height_mm);
+        let (dx, dy) = (angle(i).cos(), angle(i).sin());
+        let align = if dx > 0.1 { Align::Left } else if dx < -0.1 { Align::Right } else { Align::Center };
+        let y = if dy < -0.1 { y - height_mm } else if dy.abs() <= 0.1 { y - height_mm / 2.0 } else { y };
+        text::draw_text(plotter, label, x, y, height_mm, 0.0, align);
+    }
+}
+
+/// Draw a polar grid: radial_rings concentric circles evenly spaced out to max_radius_mm, and
+/// angular_divisions spokes from the center to the outer circle, the first along +x and the rest
+/// evenly spaced CCW.  Plot polar data or a radar chart over it.
+///
+/// plotter: device to plot to.
+/// cx_mm, cy_mm: center of the grid.
+/// max_radius_mm: radius of the outer circle.
+/// radial_rings: number of circles, the outermost at max_radius_mm.
+/// angular_divisions: number of spokes.
+///
+/// # Examples
+///
+/// ```
+/// charts::polar_grid(plotter, 0.0, 0.0, 35.0, 5, 12); // Rings every 7 mm, spokes every 30 degrees.
+/// ```
+///
+pub fn polar_grid(plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64, max_radius_mm: f64,
+                  radial_rings: u32, angular_divisions: u32) {
+    if max_radius_mm <= 0.0 {
+        panic!("Parameter `max_radius_mm` must be greater than zero.")
+    }
+    for ring in 1 .. (radial_rings + 1) {
+        circle_at(plotter, cx_mm, cy_mm, max_radius_mm * ring as f64 / radial_rings as f64);
+    }
+    sunburst(plotter, cx_mm, cy_mm, 0.0, max_radius_mm, angular_divisions, 0.0);
+    plotter.pen_up();
+}
+
+/// Plot values as a pie chart, one slice per value sized by its share of the total.
+/// The first slice starts at the top and the rest follow clockwise.  Slices use colors in turn,
+/// starting again when they run out, and the pen is changed whenever the color differs from the last slice's.
+///
+/// plotter: device to plot to.
+/// values: the data, none negative and at least one greater than zero.  Zero values get no slice.
+/// colors: pen color names to draw the slices with, at least one.
+/// cx_mm, cy_mm, radius_mm: center and size of the chart.
+/// filled: true to hatch-fill each slice as well as outline it.
+///
+/// # Examples
+///
+/// ```
+/// charts::pie_chart(plotter, &[3.0, 2.0, 1.0], &["red", "green", "blue"], 0.0, 0.0, 30.0, true);
+/// ```
+///
+pub fn pie_chart(plotter: &mut impl Plottable, values: &[f64], colors: &[&str], cx_mm: f64, cy_mm: f64,
+                 radius_mm: f64, filled: bool) {
+    // Error checking.
+    if values.iter().any(|&v| v < 0.0) {
+        panic!("Parameter `values` must not contain negative values.")
+    }
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        panic!("Parameter `values` must have at least one value greater than zero.")
+    }
+    if colors.is_empty() {
+        panic!("Parameter `colors` must have at least one color.")
+    }
+
+    let mut start_rad = PI / 2.0;
+    let mut color = None;
+    for (i, &value) in values.iter().enumerate() {
+        if value == 0.0 {
+            continue;
+        }
+        let end_rad = start_rad - 2.0 * PI * value / total;
+        let slice_color = colors[i % colors.len()];
+        if color != Some(slice_color) {
+            plotter.change_color(slice_color);
+            color = Some(slice_color);
+        }
+        plotter.sector(cx_mm, cy_mm, radius_mm, start_rad, end_rad, filled);
+        start_rad = end_rad;
+    }
+    plotter.pen_up();
+}
+
+/// Plot values as a bar chart: one outlined bar per value, evenly spaced across bounds, rising from
+/// a horizontal axis at baseline_mm.  The largest value reaches the top of bounds.  Each label is
+/// written centered beneath its bar, between the baseline and the bottom of bounds, all at the
+/// same height, chosen so the longest label fits the width of its category.
+///
+/// plotter: device to plot to.
+/// values: the data, at least one value, none negative.
+/// labels: a name for each bar, or empty for no labels.
+/// bounds: (llx_mm, lly_mm, urx_mm, ury_mm) rectangle the chart and labels fill.
+/// baseline_mm: y of the axis the bars stand on, above lly_mm to leave room for the labels.
+/// filled: true to hatch-fill each bar as well as outline it.
+///
+/// # Examples
+///
+/// ```
+/// charts::bar_chart(plotter, &[4.0, 7.0, 2.5], &["MON", "TUE", "WED"], (-40.0, -40.0, 40.0, 40.0), -30.0, true);
+/// ```
+///
+pub fn bar_chart(plotter: &mut impl Plottable, values: &[f64], labels: &[&str], bounds: (f64, f64, f64, f64),
+                 baseline_mm: f64, filled: bool) {
+    let (llx, lly, urx, ury) = bounds;
+    // Error checking.
+    if values.is_empty() {
+        panic!("Parameter `values` must have at least one value.")
+    }
+    if values.iter().any(|&v| v < 0.0) {
+        panic!("Parameter `values` must not contain negative values.")
+    }
+    if !labels.is_empty() && labels.len() != values.len() {
+        panic!("Parameter `labels` must be empty or have one label per value.")
+    }
+    if baseline_mm < lly || baseline_mm >= ury {
+        panic!("Parameter `baseline_mm` must be within the bounds, below the top.")
+    }
+    let n = values.len();
+    let slot_mm = (urx - llx) / n as f64;
+    let bar_mm = slot_mm * BAR_FRACTION;
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    let scale = if max > 0.0 { (ury - baseline_mm) / max } else { 0.0 };
+
+    // Bars.
+    for (i, &value) in values.iter().enumerate() {
+        let x0 = llx + slot_mm * i as f64 + (slot_mm - bar_mm) / 2.0;
+        let top = baseline_mm + value * scale;
+        if top <= baseline_mm {
+            continue;
+        }
+        let bar = vec![(x0, baseline_mm), (x0 + bar_mm, baseline_mm), (x0 + bar_mm, top), (x0, top)];
+        if filled {
+            fill::hatch_fill(plotter, &bar, BAR_HATCH_MM, PI / 4.0);
+        }
+        draw_closed(plotter, bar);
+    }
+    // Axis.
+    plotter.move_to(llx, baseline_mm);
+    plotter.draw(urx, baseline_mm);
+
+    // Labels, a third of their height below the axis.
+    let widest = labels.iter().map(|label| text::text_width(label, 1.0)).fold(0.0, f64::max);
+    let mut height_mm = ((baseline_mm - lly) * 0.75).min(BAR_LABEL_MAX_MM);
+    if widest > 0.0 {
+        height_mm = height_mm.min(slot_mm * 0.9 / widest);
+    }
+    if height_mm > 0.0 {
+        for (i, label) in labels.iter().enumerate() {
+            let x = llx + slot_mm * (i as f64 + 0.5);
+            text::draw_text(plotter, label, x, baseline_mm - height_mm * 4.0 / 3.0, height_mm, 0.0, Align::Center);
+        }
+    }
+    plotter.pen_up();
+}
+
+/// Draw a closed polygon through points.
+fn draw_closed(plotter: &mut impl Plottable, points: Vec<(f64, f64)>) {
+    if let Some(&(x, y)) = points.first() {
+        plotter.move_to(x, y);
+        for &(x, y) in points[1 ..].iter().chain(points[.. 1].iter()) {
+            plotter.draw(x, y);
+        }
+    }
+}
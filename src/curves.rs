@@ -0,0 +1,491 @@
+//! The curves module contains code to plot curves given by explicit or parametric functions,
+//! other than the roulettes.
+//!
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use crate::plottable::Plottable;
+use crate::rng::Rng;
+use crate::roulette::Translator;
+use crate::text;
+
+const BEZIER_TABLE_SIZE: usize = 200; // Chords used to measure arc length along a Bézier curve.
+const SPIRAL_STEPS_PER_TURN: f64 = 120.0; // Segments in each turn of a spiral.
+const ROSE_STEPS_PER_PETAL: f64 = 40.0; // Segments in each pass through a petal of a rose.
+
+/// Draw a sine wave along the x axis:
+///     y = y_center_mm + amplitude_mm * sin(2π (x - x_start_mm) / wavelength_mm + phase_rad)
+///
+/// plotter: device to plot to.
+/// x_start_mm, x_end_mm: extent of the wave.  The wave starts at phase_rad at x_start_mm.
+/// samples: number of points along the wave, at least 2.  About 20 per wavelength looks smooth.
+///
+pub fn sine_wave(plotter: &mut impl Plottable, amplitude_mm: f64, wavelength_mm: f64, phase_rad: f64,
+                 x_start_mm: f64, x_end_mm: f64, y_center_mm: f64, samples: u32) {
+    // Error checking.
+    if samples < 2 {
+        panic!("Parameter `samples` must be at least 2.")
+    }
+    if wavelength_mm <= 0.0 {
+        panic!("Parameter `wavelength_mm` must be greater than zero.")
+    }
+
+    for i in 0 .. samples {
+        let x = x_start_mm + (x_end_mm - x_start_mm) * i as f64 / (samples - 1) as f64;
+        let y = y_center_mm + amplitude_mm * (2.0 * PI * (x - x_start_mm) / wavelength_mm + phase_rad).sin();
+        if i == 0 {
+            plotter.move_to(x, y);
+        } else {
+            plotter.draw(x, y);
+        }
+    }
+}
+
+/// Draw a Lissajous figure:
+///     x = amp_x_mm * sin(freq_x * t + phase_rad),  y = amp_y_mm * sin(freq_y * t)
+/// for t from 0 to 2π, centered on (centerx_mm, centery_mm).  With whole-number frequencies the
+/// curve closes; their ratio sets the number of lobes, and phase_rad the shape.
+///
+/// plotter: device to plot to.
+/// amp_x_mm, amp_y_mm: half the width and height of the figure.
+/// freq_x, freq_y: frequencies of the x and y motions.
+/// steps: number of segments, at least 3.  Allow 50 or so per lobe for a smooth curve.
+///
+/// # Examples
+///
+/// ```
+/// curves::lissajous(plotter, 30.0, 30.0, 3.0, 2.0, PI / 2.0, 0.0, 0.0, 300);
+/// ```
+///
+pub fn lissajous(plotter: &mut impl Plottable, amp_x_mm: f64, amp_y_mm: f64, freq_x: f64, freq_y: f64,
+                 phase_rad: f64, centerx_mm: f64, centery_mm: f64, steps: u32) {
+    if steps < 3 {
+        panic!("Parameter `steps` must be at least 3.")
+    }
+    let trans = Translator {centerx_mm, centery_mm, rot_rad: 0.0};
+    for i in 0 .. (steps + 1) {
+        let t = 2.0 * PI * i as f64 / steps as f64;
+        let (x, y) = trans.translate(amp_x_mm * (freq_x * t + phase_rad).sin(), amp_y_mm * (freq_y * t).sin());
+        if i == 0 {
+            plotter.move_to(x, y);
+        } else {
+            plotter.draw(x, y);
+        }
+    }
+}
+
+/// Draw an Archimedean spiral, r = a_mm + b_mm * θ, whose turns are evenly spaced 2π b_mm apart.
+/// θ runs from 0 to 2π turns, so the spiral starts on the +x axis (turned by rot_rad) at radius a_mm.
+///
+/// plotter: device to plot to.
+/// a_mm: starting radius.
+/// b_mm: growth in radius per radian.  Negative winds clockwise instead of CCW.
+/// turns: number of turns, which may be fractional.
+/// centerx_mm, centery_mm, rot_rad: center and rotation of the spiral.
+///
+/// # Examples
+///
+/// ```
+/// curves::archimedean_spiral(plotter, 0.0, 1.0, 5.0, 0.0, 0.0, 0.0); // Turns 6.3 mm apart.
+/// ```
+///
+pub fn archimedean_spiral(plotter: &mut impl Plottable, a_mm: f64, b_mm: f64, turns: f64,
+                          centerx_mm: f64, centery_mm: f64, rot_rad: f64) {
+    spiral_with_steps(plotter, |theta| a_mm + b_mm * theta, turns, centerx_mm, centery_mm, rot_rad,
+                      SPIRAL_STEPS_PER_TURN as u32);
+}
+
+/// Draw a logarithmic spiral, r = a_mm * e^(growth θ), which grows by the same factor, e^(2π growth),
+/// each turn.  θ runs from 0 to 2π turns, starting on the +x axis (turned by rot_rad) at radius a_mm.
+///
+/// plotter: device to plot to.
+/// a_mm: starting radius.
+/// growth: rate of growth per radian.  Negative spirals inwards.
+/// turns: number of turns, which may be fractional.
+/// centerx_mm, centery_mm, rot_rad: center and rotation of the spiral.
+///
+/// # Examples
+///
+/// ```
+/// curves::logarithmic_spiral(plotter, 1.0, 0.1, 5.0, 0.0, 0.0, 0.0); // Ends at about 23 mm.
+/// ```
+///
+pub fn logarithmic_spiral(plotter: &mut impl Plottable, a_mm: f64, growth: f64, turns: f64,
+                          centerx_mm: f64, centery_mm: f64, rot_rad: f64) {
+    spiral_with_steps(plotter, |theta| a_mm * (growth * theta).exp(), turns, centerx_mm, centery_mm, rot_rad,
+                      SPIRAL_STEPS_PER_TURN as u32);
+}
+
+/// Draw any spiral given its radius as a function of θ, in steps_per_turn segments per turn
+/// (archimedean_spiral() and logarithmic_spiral() use 120).  θ runs from 0 to 2π turns and the
+/// last step is shortened to end exactly there, so fractional turns are drawn in full.
+///
+/// # Examples
+///
+/// ```
+/// // Fermat's spiral, more finely than the default.
+/// curves::spiral_with_steps(plotter, |theta| 3.0 * theta.sqrt(), 4.0, 0.0, 0.0, 0.0, 360);
+/// ```
+///
+pub fn spiral_with_steps(plotter: &mut impl Plottable, radius_mm: impl Fn(f64) -> f64, turns: f64,
+                         centerx_mm: f64, centery_mm: f64, rot_rad: f64, steps_per_turn: u32) {
+    if turns <= 0.0 {
+        panic!("Parameter `turns` must be greater than zero.")
+    }
+    if steps_per_turn < 3 {
+        panic!("Parameter `steps_per_turn` must be at least 3.")
+    }
+    let trans = Translator {centerx_mm, centery_mm, rot_rad};
+    let end_rad = 2.0 * PI * turns;
+    let steps = (turns * steps_per_turn as f64).ceil() as u32;
+    for i in 0 .. (steps + 1) {
+        let theta = (2.0 * PI * i as f64 / steps_per_turn as f64).min(end_rad);
+        let r = radius_mm(theta);
+        let (x, y) = trans.translate(r * theta.cos(), r * theta.sin());
+        if i == 0 {
+            plotter.move_to(x, y);
+        } else {
+            plotter.draw(x, y);
+        }
+    }
+}
+
+/// Draw a rose (rhodonea) curve, r = amplitude_mm * cos(k θ) with k = k_numerator / k_denominator.
+/// θ runs over one full period, so the curve closes: with k = n/d in lowest terms that is π d when
+/// n and d are both odd, and 2π d otherwise.  A whole-number k gives k petals when odd and 2k when even.
+///
+/// plotter: device to plot to.
+/// amplitude_mm: length of the petals.
+/// k_numerator, k_denominator: k as a fraction; the denominator must be greater than zero.
+/// centerx_mm, centery_mm, rot_rad: center and rotation of the rose.  The first petal points along +x
+/// turned by rot_rad.
+///
+/// # Examples
+///
+/// ```
+/// curves::rose(plotter, 30.0, 3, 1, 0.0, 0.0, 0.0); // Three petals.
+/// curves::rose(plotter, 30.0, 5, 4, 0.0, 0.0, 0.0); // Overlapping petals, over 4 turns.
+/// ```
+///
+pub fn rose(plotter: &mut impl Plottable, amplitude_mm: f64, k_numerator: i32, k_denominator: i32,
+            centerx_mm: f64, centery_mm: f64, rot_rad: f64) {
+    if k_denominator <= 0 {
+        panic!("Parameter `k_denominator` must be greater than zero.")
+    }
+    // Reduce k to lowest terms.  cos is even, so the sign of the numerator doesn't matter.
+    let (mut a, mut b) = (k_numerator.abs(), k_denominator);
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    let (n, d) = (k_numerator.abs() / a, k_denominator / a);
+    let half_turns = if n % 2 == 1 && d % 2 == 1 { d } else { 2 * d }; // Period in units of π.
+    let period_rad = PI * half_turns as f64;
+    let k = n as f64 / d as f64;
+    let steps = (half_turns as f64 * n.max(1) as f64 * ROSE_STEPS_PER_PETAL) as u32;
+
+    let trans = Translator {centerx_mm, centery_mm, rot_rad};
+    for i in 0 .. (steps + 1) {
+        let theta = period_rad * i as f64 / steps as f64;
+        let r = amplitude_mm * (k * theta).cos();
+        let (x, y) = trans.translate(r * theta.cos(), r * theta.sin());
+        if i == 0 {
+            plotter.move_to(x, y);
+        } else {
+            plotter.draw(x, y);
+        }
+    }
+}
+
+/// Draw flow-field streamlines: short curves that follow the direction given by a field.
+///
+/// plotter: device to plot to.
+/// field: direction of flow in radians at each point (x_mm, y_mm).
+/// bounds: area to draw in, as (llx_mm, lly_mm, urx_mm, ury_mm).  Streamlines stop at its edge.
+/// line_count: number of streamlines, each started at a random point.
+/// step_mm: length of each step along a streamline.  Smaller is smoother.
+/// max_steps: longest a streamline can be, in steps.
+/// seed: seed for the starting points.  The same seed gives the same plot.
+///
+/// # Examples
+///
+/// ```
+/// // Swirl around the origin.
+/// flow_field(&mut plotter, |(x, y)| y.atan2(x) + PI / 2.0 + 0.3, (-40.0, -40.0, 40.0, 40.0), 300, 0.5, 60, 1);
+/// ```
+///
+pub fn flow_field(plotter: &mut impl Plottable, field: impl Fn((f64, f64)) -> f64, bounds: (f64, f64, f64, f64),
+                  line_count: u32, step_mm: f64, max_steps: u32, seed: u64) {
+    let (llx, lly, urx, ury) = bounds;
+    let inside = |x: f64, y: f64| x >= llx && x <= urx && y >= lly && y <= ury;
+    let mut rng = Rng::new(seed);
+
+    for _ in 0 .. line_count {
+        let (mut x, mut y) = (rng.range(llx, urx), rng.range(lly, ury));
+        plotter.move_to(x, y);
+        for _ in 0 .. max_steps {
+            // Midpoint (second order Runge-Kutta) step, so curves don't spiral outwards.
+            let a = field((x, y));
+            let (mx, my) = (x + 0.5 * step_mm * a.cos(), y + 0.5 * step_mm * a.sin());
+            let a = field((mx, my));
+            let (nx, ny) = (x + step_mm * a.cos(), y + step_mm * a.sin());
+            if !inside(nx, ny) {
+                break;
+            }
+            plotter.draw(nx, ny);
+            x = nx;
+            y = ny;
+        }
+    }
+}
+
+/// Write text along an Archimedean spiral, starting one turn out from the center and winding
+/// clockwise outwards, with each character standing upright on the spiral.
+///
+/// plotter: device to plot to.
+/// cx_mm, cy_mm: center of the spiral.
+/// spacing_mm: distance between turns.  Should be more than height_mm, or the turns overlap.
+/// height_mm: height of capital letters.
+///
+pub fn text_spiral(plotter: &mut impl Plottable, text: &str, cx_mm: f64, cy_mm: f64, spacing_mm: f64, height_mm: f64) {
+    // r = b * phi, and the point at phi is at angle -phi so the spiral runs clockwise.
+    let b = spacing_mm / (2.0 * PI);
+    let mut phi = 2.0 * PI;
+    for c in text.chars() {
+        let r = b * phi;
+        let (sin, cos) = phi.sin_cos();
+        let (x, y) = (cx_mm + r * cos, cy_mm - r * sin);
+        // The baseline follows the tangent, which leaves the tops of the letters facing outwards.
+        let tangent = (-b * sin - r * cos).atan2(b * cos - r * sin);
+        let step = text::draw_char(plotter, c, x, y, height_mm, tangent);
+        phi += step / (r * r + b * b).sqrt(); // Arc length to angle.
+    }
+}
+
+/// Draw a cubic Bézier curve with the points spaced evenly along it, rather than evenly in the
+/// curve parameter t, which bunches points up on gentle stretches and spreads them out on
+/// tight bends.
+/// https://en.wikipedia.org/wiki/B%C3%A9zier_curve#Cubic_B%C3%A9zier_curves
+///
+/// The parameter is mapped to arc length with a table of BEZIER_TABLE_SIZE chords, and each
+/// point's t is interpolated from the table.
+///
+/// plotter: device to plot to.
+/// p0, p3: end points of the curve, in mm.
+/// p1, p2: control points.  The curve leaves p0 heading towards p1 and arrives at p3 from p2.
+/// step_mm: largest distance along the curve between points.
+///
+pub fn cubic_bezier_even(plotter: &mut impl Plottable, p0: (f64, f64), p1: (f64, f64), p2: (f64, f64),
+                         p3: (f64, f64), step_mm: f64) {
+    // Error checking.
+    if step_mm <= 0.0 {
+        panic!("Parameter `step_mm` must be greater than zero.")
+    }
+
+    let point = |t: f64| {
+        let u = 1.0 - t;
+        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        (a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0, a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1)
+    };
+
+    // Arc length at t = i / BEZIER_TABLE_SIZE.
+    let mut lengths = Vec::with_capacity(BEZIER_TABLE_SIZE + 1);
+    lengths.push(0.0);
+    let mut last = p0;
+    for i in 1 .. (BEZIER_TABLE_SIZE + 1) {
+        let p = point(i as f64 / BEZIER_TABLE_SIZE as f64);
+        lengths.push(lengths[i - 1] + (p.0 - last.0).hypot(p.1 - last.1));
+        last = p;
+    }
+    let total = lengths[BEZIER_TABLE_SIZE];
+
+    let steps = ((total / step_mm).ceil() as usize).max(1);
+    plotter.move_to(p0.0, p0.1);
+    let mut i = 0;
+    for k in 1 .. steps {
+        let target = total * k as f64 / steps as f64;
+        while lengths[i + 1] < target {
+            i += 1;
+        }
+        let fraction = (target - lengths[i]) / (lengths[i + 1] - lengths[i]);
+        let (x, y) = point((i as f64 + fraction) / BEZIER_TABLE_SIZE as f64);
+        plotter.draw(x, y);
+    }
+    plotter.draw(p3.0, p3.1);
+}
+
+/// Draw a Chladni figure: the nodal lines of a vibrating square plate, where sand collects
+/// when the plate is bowed.  These are the zero contours of
+///     cos(nπx) cos(mπy) - cos(mπx) cos(nπy)
+/// with x and y running from 0 to 1 across bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+/// https://en.wikipedia.org/wiki/Ernst_Chladni#Chladni_figures
+///
+/// plotter: device to plot to.
+/// m, n: mode numbers of the vibration; must differ.  Integers give the classic symmetric
+///       figures; the figure gets busier as they grow.
+/// grid: number of cells the function is sampled on along each side.  A few times 10 × max(m, n)
+///       gives smooth lines.
+///
+pub fn chladni(plotter: &mut impl Plottable, m: f64, n: f64, bounds: (f64, f64, f64, f64), grid: u32) {
+    // Error checking.
+    if m == n {
+        panic!("Parameters `m` and `n` must differ, or the plate doesn't move.")
+    }
+    if grid < 2 {
+        panic!("Parameter `grid` must be at least 2.")
+    }
+    let (llx, lly, urx, ury) = bounds;
+    let plate = |x_mm: f64, y_mm: f64| {
+        let (x, y) = ((x_mm - llx) / (urx - llx), (y_mm - lly) / (ury - lly));
+        (n * PI * x).cos() * (m * PI * y).cos() - (m * PI * x).cos() * (n * PI * y).cos()
+    };
+    for path in zero_contours(plate, bounds, grid as usize) {
+        plotter.move_to(path[0].0, path[0].1);
+        for &(x, y) in &path[1 ..] {
+            plotter.draw(x, y);
+        }
+    }
+}
+
+/// Where a contour crosses a grid edge: (vertical, i, j) for the edge going up (vertical) or
+/// right from grid point (i, j).
+type EdgeKey = (bool, usize, usize);
+
+/// Trace the curves where f(x_mm, y_mm) = 0 within bounds by marching squares over a
+/// grid × grid sampling, joined into paths.  Closed curves start and end at the same point.
+/// https://en.wikipedia.org/wiki/Marching_squares
+fn zero_contours<F: Fn(f64, f64) -> f64>(f: F, bounds: (f64, f64, f64, f64), grid: usize) -> Vec<Vec<(f64, f64)>> {
+    let (llx, lly, urx, ury) = bounds;
+    let x_at = |i: usize| llx + (urx - llx) * i as f64 / grid as f64;
+    let y_at = |j: usize| lly + (ury - lly) * j as f64 / grid as f64;
+    let values: Vec<Vec<f64>> = (0 .. grid + 1).map(|i| (0 .. grid + 1).map(|j| f(x_at(i), y_at(j))).collect()).collect();
+
+    // Crossing point on an edge, interpolated between the values at its ends.
+    let crossing = |(vertical, i, j): EdgeKey| {
+        let (i2, j2) = if vertical { (i, j + 1) } else { (i + 1, j) };
+        let (a, b) = (values[i][j], values[i2][j2]);
+        let t = a / (a - b);
+        (x_at(i) + t * (x_at(i2) - x_at(i)), y_at(j) + t * (y_at(j2) - y_at(j)))
+    };
+
+    // Each crossing is linked to the one or two crossings it joins in neighboring cells.
+    let mut links: HashMap<EdgeKey, Vec<EdgeKey>> = HashMap::new();
+    let mut link = |a: EdgeKey, b: EdgeKey| {
+        links.entry(a).or_insert_with(Vec::new).push(b);
+        links.entry(b).or_insert_with(Vec::new).push(a);
+    };
+    for i in 0 .. grid {
+        for j in 0 .. grid {
+            // Corners counterclockwise from lower left, and the edges after each.
+            let corners = [values[i][j], values[i + 1][j], values[i + 1][j + 1], values[i][j + 1]];
+            let edges = [(false, i, j), (true, i + 1, j), (false, i, j + 1), (true, i, j)];
+            let above: Vec<bool> = corners.iter().map(|&v| v >= 0.0).collect();
+            let crossed: Vec<EdgeKey> = (0 .. 4).filter(|&k| above[k] != above[(k + 1) % 4]).map(|k| edges[k]).collect();
+            match crossed.len() {
+                2 => link(crossed[0], crossed[1]),
+                4 => {
+                    // Saddle: opposite corners agree.  The center decides which pair is joined.
+                    let center = corners.iter().sum::<f64>() / 4.0;
+                    if (center >= 0.0) == above[0] {
+                        link(edges[0], edges[1]); // Cut off the lower right corner...
+                        link(edges[2], edges[3]); // ...and the upper left.
+                    } else {
+                        link(edges[3], edges[0]); // Cut off the lower left corner...
+                        link(edges[1], edges[2]); // ...and the upper right.
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Walk the links into paths, open ones (starting at an end) first, then closed loops.
+    let mut paths = Vec::new();
+    let mut keys: Vec<EdgeKey> = links.keys().cloned().collect();
+    keys.sort_by_key(|k| links[k].len());
+    for start in keys {
+        if links[&start].is_empty() {
+            continue;
+        }
+        let mut path = vec![crossing(start)];
+        let mut here = start;
+        while let Some(next) = links.get_mut(&here).and_then(|l| l.pop()) {
+            let back = links.get_mut(&next).unwrap();
+            let k = back.iter().position(|&b| b == here).unwrap();
+            back.swap_remove(k);
+            path.push(crossing(next));
+            here = next;
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockPlotter;
+
+    fn near(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).hypot(a.1 - b.1) < 1e-9
+    }
+
+    #[test]
+    fn lissajous_with_whole_frequencies_closes() {
+        let mut mock = MockPlotter::new(-50.0, -50.0, 50.0, 50.0);
+        lissajous(&mut mock, 30.0, 20.0, 3.0, 2.0, PI / 4.0, 5.0, -3.0, 300);
+        let points = mock.points();
+        assert_eq!(points.len(), 301);
+        assert!(near(points[0], points[300]), "{:?} {:?}", points[0], points[300]);
+        assert!(!near(points[0], points[150])); // Not closed after half the curve.
+    }
+
+    #[test]
+    fn spirals_end_at_the_formula_radius() {
+        // 2.51 turns, so the last step is shortened to end exactly there.
+        let (turns, cx, cy, rot) = (2.51, 5.0, -3.0, 0.3);
+        let end_rad = 2.0 * PI * turns;
+        let end_point = |r: f64| (cx + r * (end_rad + rot).cos(), cy + r * (end_rad + rot).sin());
+
+        let mut mock = MockPlotter::new(-50.0, -50.0, 50.0, 50.0);
+        archimedean_spiral(&mut mock, 2.0, 1.5, turns, cx, cy, rot);
+        let last = *mock.points().last().unwrap();
+        assert!(near(last, end_point(2.0 + 1.5 * end_rad)), "{:?}", last);
+
+        let mut mock = MockPlotter::new(-50.0, -50.0, 50.0, 50.0);
+        logarithmic_spiral(&mut mock, 1.0, 0.1, turns, cx, cy, rot);
+        let last = *mock.points().last().unwrap();
+        assert!(near(last, end_point((0.1 * end_rad).exp())), "{:?}", last);
+    }
+
+    /// The distinct petal tips of a rose drawn into mock: points at the full amplitude.
+    fn petal_tips(mock: &MockPlotter, amplitude_mm: f64) -> Vec<(f64, f64)> {
+        let mut tips: Vec<(f64, f64)> = Vec::new();
+        for p in mock.points() {
+            if (p.0.hypot(p.1) - amplitude_mm).abs() < 1e-9 && !tips.iter().any(|&t| near(t, p)) {
+                tips.push(p);
+            }
+        }
+        tips
+    }
+
+    #[test]
+    fn roses_close_after_their_period() {
+        // k = 3: three petals, closed after π, so well within 2π.
+        let mut mock = MockPlotter::new(-50.0, -50.0, 50.0, 50.0);
+        rose(&mut mock, 30.0, 3, 1, 0.0, 0.0, 0.0);
+        let points = mock.points();
+        assert!(near(points[0], points[points.len() - 1]));
+        assert_eq!(petal_tips(&mock, 30.0).len(), 3);
+
+        // k = 2: four petals, and the one along -x is only reached at θ = π, so the full 2π is needed.
+        let mut mock = MockPlotter::new(-50.0, -50.0, 50.0, 50.0);
+        rose(&mut mock, 30.0, 2, 1, 0.0, 0.0, 0.0);
+        let points = mock.points();
+        assert!(near(points[0], points[points.len() - 1]));
+        let tips = petal_tips(&mock, 30.0);
+        assert_eq!(tips.len(), 4);
+        assert!(tips.iter().any(|&t| near(t, (-30.0, 0.0))));
+    }
+}
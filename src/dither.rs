@@ -0,0 +1,93 @@
+//! The dither module contains code to plot grayscale images as fields of dots.
+//! https://en.wikipedia.org/wiki/Floyd%E2%80%93Steinberg_dithering
+//!
+
+use crate::plottable::Plottable;
+
+const MAX_DOTS: u32 = 20_000; // Every dot is a pen lift, so more than this takes hours.
+
+/// How to decide which pixels get a dot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DitherMode {
+    /// A dot wherever the pixel is darker than the level (0 = black, 255 = white).
+    Threshold(u8),
+    /// Floyd-Steinberg error diffusion, so the density of dots follows the shade of gray.
+    FloydSteinberg,
+}
+
+/// Plot a grayscale image as dots, one per dark pixel.
+///
+/// plotter: device to plot to.
+/// pixels: rows of pixel values, top row first, 0 = black and 255 = white.  Rows should be the same length.
+/// bounds: rectangle to fit the image in, as (llx_mm, lly_mm, urx_mm, ury_mm).  The image keeps its
+/// proportions and is centered.
+/// mode: how to choose the dark pixels.
+///
+/// Plotting stops after MAX_DOTS dots, so use a small image (around 100 x 100 pixels).
+/// Rows are plotted alternately left to right and right to left to save pen travel.
+pub fn plot_image_dither(plotter: &mut impl Plottable, pixels: &[Vec<u8>], bounds: (f64, f64, f64, f64), mode: DitherMode) {
+    let rows = pixels.len();
+    let cols = pixels.iter().map(|row| row.len()).max().unwrap_or(0);
+    if rows == 0 || cols == 0 {
+        return;
+    }
+    let dark = match mode {
+        DitherMode::Threshold(level) => pixels.iter()
+            .map(|row| (0 .. cols).map(|c| row.get(c).map_or(false, |&p| p < level)).collect())
+            .collect(),
+        DitherMode::FloydSteinberg => floyd_steinberg(pixels, cols),
+    };
+
+    // Fit the image in the bounds.
+    let (llx, lly, urx, ury) = bounds;
+    let pitch = ((urx - llx) / cols as f64).min((ury - lly) / rows as f64); // mm per pixel.
+    let left = (llx + urx) / 2.0 - pitch * cols as f64 / 2.0;
+    let top = (lly + ury) / 2.0 + pitch * rows as f64 / 2.0;
+
+    let mut count = 0;
+    for (r, row) in dark.iter().enumerate() {
+        let y = top - (r as f64 + 0.5) * pitch;
+        let order: Vec<usize> = if r % 2 == 0 { (0 .. cols).collect() } else { (0 .. cols).rev().collect() };
+        for c in order {
+            if row[c] {
+                if count == MAX_DOTS {
+                    println!("Stopping image at {} dots.", MAX_DOTS);
+                    return;
+                }
+                plotter.dot(left + (c as f64 + 0.5) * pitch, y);
+                count += 1;
+            }
+        }
+    }
+}
+
+/// Floyd-Steinberg dithering to black and white.  Returns true for black pixels.
+fn floyd_steinberg(pixels: &[Vec<u8>], cols: usize) -> Vec<Vec<bool>> {
+    let mut levels: Vec<Vec<f64>> = pixels.iter()
+        .map(|row| (0 .. cols).map(|c| *row.get(c).unwrap_or(&255) as f64).collect())
+        .collect();
+    let rows = levels.len();
+    let mut dark = vec![vec![false; cols]; rows];
+    for r in 0 .. rows {
+        for c in 0 .. cols {
+            let old = levels[r][c];
+            let new = if old < 128.0 { 0.0 } else { 255.0 };
+            dark[r][c] = new == 0.0;
+            // Pass the error on to the neighbors not yet visited.
+            let error = old - new;
+            if c + 1 < cols {
+                levels[r][c + 1] += error * 7.0 / 16.0;
+            }
+            if r + 1 < rows {
+                if c > 0 {
+                    levels[r + 1][c - 1] += error * 3.0 / 16.0;
+                }
+                levels[r + 1][c] += error * 5.0 / 16.0;
+                if c + 1 < cols {
+                    levels[r + 1][c + 1] += error * 1.0 / 16.0;
+                }
+            }
+        }
+    }
+    dark
+}
@@ -0,0 +1,283 @@
+//! The fill module contains code to cover areas with lines.
+//!
+
+use crate::geometry::{clip_line, inset_polygon, point_in_polygon, reverse_path};
+use crate::plottable::Plottable;
+use crate::rng::Rng;
+
+const MAX_STIPPLE_DOTS: u32 = 20_000; // Every dot is a pen lift, so more than this takes hours.
+
+/// Draw parallel lines spacing_mm apart at angle_rad (0 = horizontal, positive is CCW),
+/// covering the rectangle bounds = (llx_mm, lly_mm, urx_mm, ury_mm).  Each line is clipped
+/// to the rectangle.  One line always passes through the center of the rectangle.
+/// Alternate lines are drawn in opposite directions (boustrophedon), so the pen moves
+/// only a short way between the end of one line and the start of the next.
+pub fn parallel_lines(plotter: &mut impl Plottable, bounds: (f64, f64, f64, f64), spacing_mm: f64, angle_rad: f64) {
+    if spacing_mm <= 0.0 {
+        panic!("Parameter `spacing_mm` must be greater than zero.")
+    }
+    let (llx, lly, urx, ury) = bounds;
+    let (cx, cy) = ((llx + urx) / 2.0, (lly + ury) / 2.0);
+    let (dx, dy) = (angle_rad.cos(), angle_rad.sin()); // Along the lines.
+    let (nx, ny) = (-dy, dx);                          // Across the lines.
+    let half_diag = 0.5 * ((urx - llx).powi(2) + (ury - lly).powi(2)).sqrt();
+
+    // Every corner is within half_diag of the center, so lines this far out in either direction cover it.
+    let count = (half_diag / spacing_mm).floor() as i32;
+    let mut forward = true;
+    for k in -count .. (count + 1) {
+        let (px, py) = (cx + nx * spacing_mm * k as f64, cy + ny * spacing_mm * k as f64);
+        if let Some((x0, y0, x1, y1)) = clip_line(px - dx * half_diag, py - dy * half_diag,
+                                                  px + dx * half_diag, py + dy * half_diag, bounds) {
+            let line = [(x0, y0), (x1, y1)];
+            if forward {
+                plotter.draw_path(&line);
+            } else {
+                plotter.draw_path(&reverse_path(&line));
+            }
+            forward = !forward;
+        }
+    }
+}
+
+/// Shade the rectangle from (llx_mm, lly_mm) to (urx_mm, ury_mm) with vertical lines whose
+/// density changes steadily from density_left at the left edge to density_right at the right,
+/// giving a gradient from light to dark (or back) without any fill.  Densities are in lines per mm,
+/// e.g. 0.2 for lines 5 mm apart.  The lines are drawn back and forth (boustrophedon).
+pub fn gradient_band(plotter: &mut impl Plottable, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64,
+                     density_left: f64, density_right: f64) {
+    if density_left <= 0.0 || density_right <= 0.0 {
+        panic!("Parameters `density_left` and `density_right` must be greater than zero.")
+    }
+    let width = urx_mm - llx_mm;
+    let density = |x: f64| density_left + (density_right - density_left) * (x - llx_mm) / width;
+
+    let mut x = llx_mm;
+    let mut up = true;
+    while x <= urx_mm {
+        let (y0, y1) = if up { (lly_mm, ury_mm) } else { (ury_mm, lly_mm) };
+        plotter.move_to(x, y0);
+        plotter.draw(x, y1);
+        up = !up;
+        // Step by the spacing halfway to the next line, so the density is right between them.
+        let half_step = 0.5 / density(x);
+        x += 1.0 / density((x + half_step).min(urx_mm));
+    }
+}
+
+/// Draw a moiré pattern: two sets of parallel lines spacing_mm apart over the rectangle
+/// bounds = (llx_mm, lly_mm, urx_mm, ury_mm), one at angle_a_rad and one at angle_b_rad.
+/// Angles a few degrees apart give broad interference bands.
+/// If second_color is given, the pen is changed to it before drawing the second set.
+pub fn moire(plotter: &mut impl Plottable, spacing_mm: f64, angle_a_rad: f64, angle_b_rad: f64,
+             bounds: (f64, f64, f64, f64), second_color: Option<&str>) {
+    parallel_lines(plotter, bounds, spacing_mm, angle_a_rad);
+    if let Some(color) = second_color {
+        plotter.change_color(color);
+    }
+    parallel_lines(plotter, bounds, spacing_mm, angle_b_rad);
+}
+
+/// One run of a hatch line inside a polygon, in the rotated frame where hatch lines are horizontal.
+/// Each end records its u coordinate and the index of the polygon edge it lies on.
+struct Span {
+    line: usize,
+    v: f64,
+    left: (f64, usize),
+    right: (f64, usize),
+}
+
+/// Fill a closed polygon with hatch lines spacing_mm apart at angle_rad (0 = horizontal,
+/// positive is CCW).  The polygon is given by its vertices; the closing edge is implied.
+/// Concave polygons and polygons with overlapping parts (even-odd rule) are handled.
+/// The outline itself is not drawn.
+///
+/// The lines are drawn back and forth (boustrophedon).  Where the end of one line and the
+/// start of the next lie on the same polygon edge, the pen stays down and follows the edge
+/// between them, so a simple region is filled in one continuous stroke.  When a stroke can't
+/// continue, the next one starts from the nearest undrawn line end.
+pub fn hatch_fill(plotter: &mut impl Plottable, polygon: &[(f64, f64)], spacing_mm: f64, angle_rad: f64) {
+    if spacing_mm <= 0.0 {
+        panic!("Parameter `spacing_mm` must be greater than zero.")
+    }
+    if polygon.len() < 3 {
+        return;
+    }
+    let (sin, cos) = angle_rad.sin_cos();
+    let to_uv = |(x, y): (f64, f64)| (x * cos + y * sin, -x * sin + y * cos);
+    let to_xy = |u: f64, v: f64| (u * cos - v * sin, u * sin + v * cos);
+    let poly: Vec<(f64, f64)> = polygon.iter().map(|&p| to_uv(p)).collect();
+
+    // Cut the polygon with horizontal lines at multiples of the spacing.
+    let vmin = poly.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let vmax = poly.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    let mut spans = Vec::new();
+    for (line, k) in ((vmin / spacing_mm).ceil() as i32 ..= (vmax / spacing_mm).floor() as i32).enumerate() {
+        let v = k as f64 * spacing_mm;
+        let mut crossings: Vec<(f64, usize)> = Vec::new();
+        for e in 0 .. poly.len() {
+            let (a, b) = (poly[e], poly[(e + 1) % poly.len()]);
+            if (a.1 <= v) != (b.1 <= v) {
+                crossings.push((a.0 + (v - a.1) / (b.1 - a.1) * (b.0 - a.0), e));
+            }
+        }
+        crossings.sort_by(|p, q| p.0.partial_cmp(&q.0).unwrap());
+        for pair in crossings.chunks(2).filter(|pair| pair.len() == 2) {
+            spans.push(Span { line, v, left: pair[0], right: pair[1] });
+        }
+    }
+
+    // Chain the spans into as few strokes as possible.
+    let mut used = vec![false; spans.len()];
+    let mut pen = poly[0];
+    while let Some((mut i, mut at_right)) = nearest_span_end(&spans, &used, pen) {
+        // Start a stroke at one end of span i.
+        let start = if at_right { spans[i].right.0 } else { spans[i].left.0 };
+        let (x, y) = to_xy(start, spans[i].v);
+        plotter.move_to(x, y);
+        loop {
+            // Across span i.
+            used[i] = true;
+            let end = if at_right { spans[i].left } else { spans[i].right };
+            let (x, y) = to_xy(end.0, spans[i].v);
+            plotter.draw(x, y);
+            at_right = !at_right;
+            pen = (end.0, spans[i].v);
+            // Along the polygon edge to a span on a neighboring line, if one starts on the same edge.
+            let next = (0 .. spans.len()).find(|&j| {
+                !used[j] && (spans[j].line == spans[i].line + 1 || spans[j].line + 1 == spans[i].line)
+                    && (if at_right { spans[j].right.1 } else { spans[j].left.1 }) == end.1
+            });
+            match next {
+                Some(j) => {
+                    let start = if at_right { spans[j].right.0 } else { spans[j].left.0 };
+                    let (x, y) = to_xy(start, spans[j].v);
+                    plotter.draw(x, y);
+                    i = j;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+/// The unused span with an end nearest to pen (in the rotated frame), and whether that end is the right one.
+fn nearest_span_end(spans: &[Span], used: &[bool], pen: (f64, f64)) -> Option<(usize, bool)> {
+    let dist = |u: f64, v: f64| (u - pen.0).powi(2) + (v - pen.1).powi(2);
+    let mut best: Option<(usize, bool, f64)> = None;
+    for (i, span) in spans.iter().enumerate().filter(|&(i, _)| !used[i]) {
+        for &(at_right, u) in [(false, span.left.0), (true, span.right.0)].iter() {
+            let d = dist(u, span.v);
+            if best.map_or(true, |(_, _, best_d)| d < best_d) {
+                best = Some((i, at_right, d));
+            }
+        }
+    }
+    best.map(|(i, at_right, _)| (i, at_right))
+}
+
+/// Fill a closed polygon with randomly scattered dots (stippling), about density_per_mm2 dots
+/// per square mm.  Random points in the polygon's bounding box are kept if they fall inside it.
+/// The number of dots tried is capped at MAX_STIPPLE_DOTS.
+/// seed: seed for the random positions.  The same seed gives the same plot.
+pub fn stipple_fill(plotter: &mut impl Plottable, polygon: &[(f64, f64)], density_per_mm2: f64, seed: u64) {
+    if polygon.len() < 3 {
+        return;
+    }
+    let minx = polygon.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let maxx = polygon.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    let miny = polygon.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let maxy = polygon.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+    let mut tries = ((maxx - minx) * (maxy - miny) * density_per_mm2).round() as u32;
+    if tries > MAX_STIPPLE_DOTS {
+        println!("Limiting stipple to {} dots.", MAX_STIPPLE_DOTS);
+        tries = MAX_STIPPLE_DOTS;
+    }
+    let mut rng = Rng::new(seed);
+    for _ in 0 .. tries {
+        let p = (rng.range(minx, maxx), rng.range(miny, maxy));
+        if point_in_polygon(p, polygon) {
+            plotter.dot(p.0, p.1);
+        }
+    }
+}
+
+/// Draw a closed outline and concentric contours inside it, each offset_step_mm inside the
+/// last, like the height lines of a topographic map.  Insetting stops after levels contours,
+/// or sooner when the shape shrinks away or the next contour would cross itself (e.g. where a
+/// concave shape pinches in two).
+pub fn contour_lines(plotter: &mut impl Plottable, outline: &[(f64, f64)], offset_step_mm: f64, levels: u32) {
+    if offset_step_mm <= 0.0 {
+        panic!("Parameter `offset_step_mm` must be greater than zero.")
+    }
+    let mut contour = outline.to_vec();
+    for level in 0 .. (levels + 1) {
+        if let Some((&first, rest)) = contour.split_first() {
+            plotter.move_to(first.0, first.1);
+            for &(x, y) in rest {
+                plotter.draw(x, y);
+            }
+            plotter.draw(first.0, first.1);
+        }
+        if level == levels {
+            break;
+        }
+        match inset_polygon(&contour, offset_step_mm) {
+            Some(inset) => contour = inset,
+            None => break,
+        }
+    }
+}
+
+/// How cell_grid() draws each filled cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillStyle {
+    /// Just the outline.
+    Outline,
+    /// Hatch lines spacing_mm apart at angle_rad, with no outline.  A spacing of about the pen
+    /// width gives solid black.
+    Hatch { spacing_mm: f64, angle_rad: f64 },
+}
+
+/// Draw a bitmap as a grid of filled squares, e.g. a QR code or pixel art.  cells[row][col] is
+/// true for a filled cell; row 0 is the top row, as in an image.  Rows may differ in length.
+///
+/// Consecutive filled cells in a row are merged into one rectangle, which is outlined or hatched
+/// as a whole, so a run of cells costs one outline or one set of hatch lines rather than one per cell.
+///
+/// plotter: device to plot to.
+/// cell_mm: side length of each cell.
+/// llx_mm, lly_mm: lower left corner of the grid.
+/// fill: how filled cells are drawn.
+///
+pub fn cell_grid(plotter: &mut impl Plottable, cells: &[Vec<bool>], cell_mm: f64, llx_mm: f64, lly_mm: f64,
+                 fill: FillStyle) {
+    let rows = cells.len();
+    for (r, row) in cells.iter().enumerate() {
+        let y0 = lly_mm + (rows - 1 - r) as f64 * cell_mm;
+        let y1 = y0 + cell_mm;
+        let mut c = 0;
+        while c < row.len() {
+            if !row[c] {
+                c += 1;
+                continue;
+            }
+            let start = c;
+            while c < row.len() && row[c] {
+                c += 1;
+            }
+            let (x0, x1) = (llx_mm + start as f64 * cell_mm, llx_mm + c as f64 * cell_mm);
+            let run = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+            match fill {
+                FillStyle::Outline => {
+                    plotter.move_to(x0, y0);
+                    for &(x, y) in run[1 ..].iter().chain(run[.. 1].iter()) {
+                        plotter.draw(x, y);
+                    }
+                }
+                FillStyle::Hatch { spacing_mm, angle_rad } => hatch_fill(plotter, &run, spacing_mm, angle_rad),
+            }
+        }
+    }
+}
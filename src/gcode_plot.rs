@@ -0,0 +1,213 @@
+//! gcode_plot module contains the GcodePlotter struct, which implements Plottable by writing
+//! G-code for pen plotters and drawbots running generic CNC firmware (e.g. GRBL), lifting the pen
+//! with the Z axis.
+//!
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::plottable::Plottable;
+
+/// Machine settings for a GcodePlotter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GcodeSettings {
+    pub pen_up_z_mm: f64,      // Z height with the pen clear of the paper.
+    pub pen_down_z_mm: f64,    // Z height with the pen on the paper.
+    pub draw_feed_mm_min: f64, // Feedrate for drawing moves (G1).
+    pub z_feed_mm_min: f64,    // Feedrate for lowering the pen.
+}
+
+impl Default for GcodeSettings {
+    fn default() -> GcodeSettings {
+        GcodeSettings { pen_up_z_mm: 5.0, pen_down_z_mm: 0.0, draw_feed_mm_min: 1500.0, z_feed_mm_min: 500.0 }
+    }
+}
+
+pub struct GcodePlotter {
+    path: PathBuf, // File written by finalize().
+    min_x_mm: f64, // Plot rectangle, in mm.
+    min_y_mm: f64,
+    max_x_mm: f64,
+    max_y_mm: f64,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    settings: GcodeSettings,
+    pen_is_down: bool,
+    program: String, // G-code so far, one command per line.
+}
+
+impl GcodePlotter {
+    /// Create a GcodePlotter for the plot rectangle with lower left corner (llx_mm, lly_mm)
+    /// and upper right corner (urx_mm, ury_mm), to be written to the file at path by finalize().
+    /// Coordinates are sent in mm as given, clipped to the plot rectangle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let settings = GcodeSettings { pen_up_z_mm: 3.0, ..GcodeSettings::default() };
+    /// let mut plotter = GcodePlotter::new(Path::new("plot.gcode"), 0.0, 0.0, 200.0, 150.0, settings);
+    /// ```
+    ///
+    pub fn new(path: &Path, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64, settings: GcodeSettings) -> GcodePlotter {
+        // Check that the upper right is greater than the lower left.
+        if (urx_mm - llx_mm <= 0.0) || (ury_mm - lly_mm <= 0.0) {
+            panic!("Error: upper right is not greater than lower left.");
+        }
+        GcodePlotter {
+            path: path.to_path_buf(),
+            min_x_mm: llx_mm,
+            min_y_mm: lly_mm,
+            max_x_mm: urx_mm,
+            max_y_mm: ury_mm,
+            pos_x_mm: llx_mm,
+            pos_y_mm: lly_mm,
+            settings,
+            pen_is_down: false,
+            program: String::new(),
+        }
+    }
+
+    /// The G-code written so far.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    fn emit(&mut self, line: &str) {
+        self.program.push_str(line);
+        self.program.push('\n');
+    }
+
+    fn lift(&mut self) {
+        if self.pen_is_down {
+            let s = format!("G0 Z{:.3}", self.settings.pen_up_z_mm);
+            self.emit(&s);
+            self.pen_is_down = false;
+        }
+    }
+
+    fn lower(&mut self) {
+        if !self.pen_is_down {
+            let s = format!("G1 Z{:.3} F{:.0}", self.settings.pen_down_z_mm, self.settings.z_feed_mm_min);
+            self.emit(&s);
+            self.pen_is_down = true;
+        }
+    }
+
+    /// Clip a point to the plot rectangle.
+    fn clip(&self, x_mm: f64, y_mm: f64) -> (f64, f64) {
+        (x_mm.max(self.min_x_mm).min(self.max_x_mm), y_mm.max(self.min_y_mm).min(self.max_y_mm))
+    }
+}
+
+impl Plottable for GcodePlotter {
+
+    /// Start the program: mm, absolute coordinates, pen up.
+    fn initialize(&mut self) {
+        self.emit("G21 ; mm");
+        self.emit("G90 ; absolute coordinates");
+        let s = format!("G0 Z{:.3}", self.settings.pen_up_z_mm);
+        self.emit(&s);
+        self.pen_is_down = false;
+    }
+
+    /// Lift the pen, end the program with M2, and write the file.
+    fn finalize(&mut self) {
+        self.lift();
+        self.emit("M2");
+        if let Err(e) = fs::write(&self.path, &self.program) {
+            eprintln!("Can't write {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Draw a straight line from present position to absolute position (destx_mm, desty_mm).
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.lower();
+        let (x, y) = self.clip(destx_mm, desty_mm);
+        let s = format!("G1 X{:.3} Y{:.3} F{:.0}", x, y, self.settings.draw_feed_mm_min);
+        self.emit(&s);
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Move without drawing to absolute position (destx_mm, desty_mm).
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.lift();
+        let (x, y) = self.clip(destx_mm, desty_mm);
+        let s = format!("G0 X{:.3} Y{:.3}", x, y);
+        self.emit(&s);
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Draw from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Move without drawing from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Raise the pen to the pen-up Z height.
+    fn pen_up(&mut self) {
+        self.lift();
+    }
+
+    /// Lower the pen to the pen-down Z height, without moving in X or Y.
+    fn pen_down(&mut self) {
+        self.lower();
+    }
+
+    /// Lift the pen and pause the program (M0) with a comment naming the pen to put in.
+    fn change_color(&mut self, color_name: &str) {
+        self.lift();
+        let s = format!("M0 ; Change to the {} pen, then resume", color_name);
+        self.emit(&s);
+    }
+
+    /// Draw at cm_per_sec from now on, by changing the G1 feedrate.
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.settings.draw_feed_mm_min = cm_per_sec * 600.0;
+    }
+
+    /// The plot rectangle given when the plotter was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_is_one_pen_down_stroke() {
+        let mut plotter = GcodePlotter::new(Path::new("unused.gcode"), 0.0, 0.0, 100.0, 100.0, GcodeSettings::default());
+        plotter.initialize();
+        plotter.move_to(10.0, 10.0);
+        for &(x, y) in &[(20.0, 10.0), (20.0, 20.0), (10.0, 20.0), (10.0, 10.0)] {
+            plotter.draw(x, y);
+        }
+        plotter.pen_up();
+        assert_eq!(plotter.program(), "G21 ; mm\n\
+                                       G90 ; absolute coordinates\n\
+                                       G0 Z5.000\n\
+                                       G0 X10.000 Y10.000\n\
+                                       G1 Z0.000 F500\n\
+                                       G1 X20.000 Y10.000 F1500\n\
+                                       G1 X20.000 Y20.000 F1500\n\
+                                       G1 X10.000 Y20.000 F1500\n\
+                                       G1 X10.000 Y10.000 F1500\n\
+                                       G0 Z5.000\n");
+    }
+}
@@ -0,0 +1,276 @@
+//! The geometry module contains helper routines shared by the plotting functions,
+//! working in mm on plain (x, y) tuples.
+//!
+
+/// Clip the line segment (x0, y0)-(x1, y1) to the rectangle bounds = (llx, lly, urx, ury),
+/// using the Liang-Barsky algorithm.  https://en.wikipedia.org/wiki/Liang%E2%80%93Barsky_algorithm
+/// Returns the part of the segment inside the rectangle, or None if it misses entirely.
+pub fn clip_line(x0: f64, y0: f64, x1: f64, y1: f64, bounds: (f64, f64, f64, f64))
+                 -> Option<(f64, f64, f64, f64)> {
+    let (llx, lly, urx, ury) = bounds;
+    let dx = x1 - x0;
+    let dy = y1 - y0;
+    let mut t0 = 0.0f64;
+    let mut t1 = 1.0f64;
+
+    // One (p, q) pair per rectangle edge: left, right, bottom, top.
+    for &(p, q) in [(-dx, x0 - llx), (dx, urx - x0), (-dy, y0 - lly), (dy, ury - y0)].iter() {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None; // Parallel to this edge and outside it.
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t); // Entering.
+            } else {
+                t1 = t1.min(t); // Leaving.
+            }
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    Some((x0 + t0 * dx, y0 + t0 * dy, x0 + t1 * dx, y0 + t1 * dy))
+}
+
+/// The same path, traced end to start.  Drawing adjoining shapes in a chosen direction
+/// controls where ink pools when the pen stops.
+pub fn reverse_path(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    points.iter().rev().cloned().collect()
+}
+
+/// Simplify a path with the Ramer-Douglas-Peucker algorithm, dropping points that deviate less
+/// than tolerance_mm from the line through their neighbors.  The end points are always kept.
+/// https://en.wikipedia.org/wiki/Ramer%E2%80%93Douglas%E2%80%93Peucker_algorithm
+pub fn simplify_path(points: &[(f64, f64)], tolerance_mm: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        // Find the point farthest from the chord first-last.
+        let mut farthest = (0.0, first);
+        for i in (first + 1) .. last {
+            let d = distance_to_segment(points[i], points[first], points[last]);
+            if d > farthest.0 {
+                farthest = (d, i);
+            }
+        }
+        if farthest.0 > tolerance_mm {
+            keep[farthest.1] = true;
+            stack.push((first, farthest.1));
+            stack.push((farthest.1, last));
+        }
+    }
+    points.iter().zip(keep.iter()).filter(|&(_, &k)| k).map(|(&p, _)| p).collect()
+}
+
+/// Points spaced evenly by arc length along a polyline, including both ends.  The spacing is
+/// adjusted from spacing_mm to the nearest that divides the length exactly, so the last point lands
+/// on the end of the path.
+pub fn points_along(points: &[(f64, f64)], spacing_mm: f64) -> Vec<(f64, f64)> {
+    let lengths: Vec<f64> = points.windows(2).map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1)).collect();
+    let total: f64 = lengths.iter().sum();
+    if total == 0.0 {
+        return points.first().into_iter().cloned().collect();
+    }
+    let count = ((total / spacing_mm).round() as usize).max(1);
+    let step = total / count as f64;
+    let mut along = Vec::with_capacity(count + 1);
+    let mut segment = 0;
+    let mut segment_start = 0.0; // Arc length at the start of the present segment.
+    for k in 0 .. (count + 1) {
+        let s = step * k as f64;
+        while segment + 1 < lengths.len() && s > segment_start + lengths[segment] {
+            segment_start += lengths[segment];
+            segment += 1;
+        }
+        let ((x0, y0), (x1, y1)) = (points[segment], points[segment + 1]);
+        let t = if lengths[segment] == 0.0 { 0.0 } else { ((s - segment_start) / lengths[segment]).min(1.0) };
+        along.push((x0 + t * (x1 - x0), y0 + t * (y1 - y0)));
+    }
+    along
+}
+
+/// Distance from point p to the line segment a-b.
+pub fn distance_to_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 { 0.0 } else { (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).max(0.0).min(1.0) };
+    ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+/// True if point p is inside the polygon (even-odd rule).  The closing edge is implied.
+pub fn point_in_polygon(p: (f64, f64), polygon: &[(f64, f64)]) -> bool {
+    let mut inside = false;
+    for i in 0 .. polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a.1 <= p.1) != (b.1 <= p.1) && p.0 < a.0 + (p.1 - a.1) / (b.1 - a.1) * (b.0 - a.0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Convex hull of a set of points, counterclockwise, by Andrew's monotone chain algorithm.
+/// https://en.wikibooks.org/wiki/Algorithm_Implementation/Geometry/Convex_hull/Monotone_chain
+/// Collinear points on the hull are left out.  Fewer than three distinct points are returned as is.
+pub fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut pts = points.to_vec();
+    pts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    pts.dedup();
+    if pts.len() < 3 {
+        return pts;
+    }
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(2 * pts.len());
+    // Lower hull, left to right, then upper hull, right to left.
+    for pass in 0 .. 2 {
+        let start = hull.len();
+        let ordered: Vec<(f64, f64)> = if pass == 0 { pts.clone() } else { pts.iter().rev().cloned().collect() };
+        for p in ordered {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop(); // The last point starts the other half.
+    }
+    hull
+}
+
+/// Signed area of a polygon: positive if its vertices run counterclockwise.  The closing edge is implied.
+pub fn signed_area(polygon: &[(f64, f64)]) -> f64 {
+    let n = polygon.len();
+    (0 .. n).map(|i| {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        a.0 * b.1 - b.0 * a.1
+    }).sum::<f64>() / 2.0
+}
+
+/// Move every edge of a polygon inwards by distance_mm, with mitered corners, and return the
+/// smaller polygon, or None once it has shrunk away or would cross itself.
+///
+/// Edges that shrink to nothing as the polygon narrows (those whose direction would reverse) are
+/// dropped and their neighbors extended to meet, so a rectangle insets to a thinner rectangle,
+/// not a bow tie.  The vertices run the same way round as the input's.
+pub fn inset_polygon(polygon: &[(f64, f64)], distance_mm: f64) -> Option<Vec<(f64, f64)>> {
+    let n = polygon.len();
+    let area = signed_area(polygon);
+    if n < 3 || area == 0.0 {
+        return None;
+    }
+    let side = area.signum(); // Inward is to the left of each edge for a counterclockwise polygon.
+
+    // Each edge as (point on the moved edge, unit direction).
+    let edges: Vec<((f64, f64), (f64, f64))> = (0 .. n).filter_map(|i| {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        let length = (b.0 - a.0).hypot(b.1 - a.1);
+        if length == 0.0 {
+            return None;
+        }
+        let u = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+        Some(((a.0 - side * u.1 * distance_mm, a.1 + side * u.0 * distance_mm), u))
+    }).collect();
+
+    // Corner where moved edges e and f meet.
+    let corner = |e: ((f64, f64), (f64, f64)), f: ((f64, f64), (f64, f64))| {
+        let (p, u) = e;
+        let (q, v) = f;
+        let denom = u.0 * v.1 - u.1 * v.0;
+        if denom.abs() < 1e-12 {
+            q // Parallel edges: they meet where the second starts.
+        } else {
+            let t = ((q.0 - p.0) * v.1 - (q.1 - p.1) * v.0) / denom;
+            (p.0 + t * u.0, p.1 + t * u.1)
+        }
+    };
+
+    let mut active: Vec<usize> = (0 .. edges.len()).collect();
+    loop {
+        if active.len() < 3 {
+            return None;
+        }
+        let m = active.len();
+        let vertices: Vec<(f64, f64)> = (0 .. m)
+            .map(|k| corner(edges[active[(k + m - 1) % m]], edges[active[k]]))
+            .collect();
+        // Edge active[k] runs from vertex k to vertex k + 1; drop any that now point backwards.
+        let kept: Vec<usize> = (0 .. m).filter(|&k| {
+            let (a, b) = (vertices[k], vertices[(k + 1) % m]);
+            let u = edges[active[k]].1;
+            (b.0 - a.0) * u.0 + (b.1 - a.1) * u.1 > 0.0
+        }).map(|k| active[k]).collect();
+        if kept.len() == m {
+            if signed_area(&vertices) * side <= 0.0 || self_intersects(&vertices) {
+                return None;
+            }
+            return Some(vertices);
+        }
+        if kept.is_empty() {
+            return None;
+        }
+        active = kept;
+    }
+}
+
+/// True if any two edges of a polygon that don't share a vertex cross each other.
+pub fn self_intersects(polygon: &[(f64, f64)]) -> bool {
+    let n = polygon.len();
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    for i in 0 .. n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        for j in (i + 2) .. n {
+            if i == 0 && j == n - 1 {
+                continue; // The closing edge shares a vertex with the first.
+            }
+            let (c, d) = (polygon[j], polygon[(j + 1) % n]);
+            if cross(a, b, c).signum() * cross(a, b, d).signum() < 0.0
+                && cross(c, d, a).signum() * cross(c, d, b).signum() < 0.0 {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Reorder paths, reversing some, to cut down the pen-up travel between them: starting with the
+/// first path as given, repeatedly go to whichever remaining path has an end nearest to where the
+/// pen is (greedy nearest neighbor), drawing it backwards if its far end is the nearer one.
+/// Not optimal, but usually most of the wasted travel is saved.  Takes time proportional to the
+/// square of the number of paths.
+pub fn optimize_travel(paths: Vec<Vec<(f64, f64)>>) -> Vec<Vec<(f64, f64)>> {
+    let mut remaining: Vec<Vec<(f64, f64)>> = paths.into_iter().filter(|p| !p.is_empty()).collect();
+    if remaining.is_empty() {
+        return remaining;
+    }
+    let distance = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).hypot(a.1 - b.1);
+    let mut ordered = Vec::with_capacity(remaining.len());
+    ordered.push(remaining.remove(0));
+    while !remaining.is_empty() {
+        let pen = *ordered.last().unwrap().last().unwrap();
+        // Nearest end of any remaining path, and whether it is the far end.
+        let (mut best, mut best_reversed, mut best_distance) = (0, false, f64::INFINITY);
+        for (i, path) in remaining.iter().enumerate() {
+            for &(end, reversed) in &[(path[0], false), (path[path.len() - 1], true)] {
+                let d = distance(pen, end);
+                if d < best_distance {
+                    best = i;
+                    best_reversed = reversed;
+                    best_distance = d;
+                }
+            }
+        }
+        let mut path = remaining.swap_remove(best);
+        if best_reversed {
+            path.reverse();
+        }
+        ordered.push(path);
+    }
+    ordered
+}
@@ -0,0 +1,133 @@
+//! hpgl module contains code to read HPGL (.plt) files, such as those exported by vector
+//! drawing tools or written by USCutter::to_file(), and to preview them with turtle graphics.
+//! Only the common subset IN, SP, PU, PD, PA, PR is understood; other commands are skipped.
+//!
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::plot_builder::{replay, PlotOp};
+use crate::plottable::Plottable;
+use crate::turtle_plot::TurtlePlotter;
+
+const MM_PER_UNIT: f64 = 0.025; // Standard HPGL plotter unit.
+
+// Pen numbers used for SP commands, in the usual HP carousel order.
+pub const PEN_NUMBERS: [(&str, u32); 10] = [
+    ("black", 1), ("red", 2), ("green", 3), ("blue", 4), ("cyan", 5),
+    ("magenta", 6), ("yellow", 7), ("brown", 8), ("orange", 9), ("purple", 10),
+];
+
+/// Show an HPGL file in a turtle graphics window, to check it before sending it to a plotter.
+/// bounds = (llx_mm, lly_mm, urx_mm, ury_mm) is the area shown, with HPGL coordinates
+/// converted to mm at 0.025 mm per plotter unit.
+pub fn preview_plt(path: &Path, bounds: (f64, f64, f64, f64)) -> io::Result<()> {
+    let text = fs::read_to_string(path)?;
+    let ops = parse_hpgl(&text);
+    let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+    let mut plotter = TurtlePlotter::new(llx_mm, lly_mm, urx_mm, ury_mm);
+    plotter.initialize();
+    replay(&ops, &mut plotter);
+    plotter.finalize();
+    Ok(())
+}
+
+/// Convert HPGL text to plot operations in mm.  Unsupported commands are skipped with a warning.
+pub fn parse_hpgl(text: &str) -> Vec<PlotOp> {
+    let mut ops = Vec::new();
+    let mut pen_is_down = false;
+    let mut relative = false;
+    let (mut x, mut y) = (0.0f64, 0.0f64); // Present position in plotter units.
+    let mut warned: Vec<String> = Vec::new();
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        // Find the next two-letter mnemonic.
+        if !(chars[i].is_ascii_alphabetic() && i + 1 < chars.len() && chars[i + 1].is_ascii_alphabetic()) {
+            i += 1;
+            continue;
+        }
+        let mnemonic: String = chars[i ..= i + 1].iter().collect::<String>().to_ascii_uppercase();
+        i += 2;
+        if mnemonic == "LB" {
+            // Label text runs to the terminator and may contain anything, so skip it whole.
+            while i < chars.len() && chars[i] != '\x03' {
+                i += 1;
+            }
+            i += 1;
+            warn_once(&mut warned, &mnemonic);
+            continue;
+        }
+        // Parameters run up to the next letter or semicolon.
+        let start = i;
+        while i < chars.len() && !chars[i].is_ascii_alphabetic() && chars[i] != ';' {
+            i += 1;
+        }
+        let params: Vec<f64> = chars[start .. i].iter().collect::<String>()
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|p| p.parse().ok())
+            .collect();
+
+        match mnemonic.as_str() {
+            "IN" => {
+                pen_is_down = false;
+                relative = false;
+                x = 0.0;
+                y = 0.0;
+            }
+            "SP" => {
+                let pen = params.first().cloned().unwrap_or(0.0) as u32;
+                if let Some(&(name, _)) = PEN_NUMBERS.iter().find(|&&(_, n)| n == pen) {
+                    ops.push(PlotOp::ChangeColor(name.to_string()));
+                }
+            }
+            "VS" => {
+                if let Some(&cm_per_sec) = params.first() {
+                    ops.push(PlotOp::SetVelocity(cm_per_sec));
+                }
+            }
+            "PU" | "PD" | "PA" | "PR" => {
+                match mnemonic.as_str() {
+                    "PU" => {
+                        pen_is_down = false;
+                        ops.push(PlotOp::PenUp);
+                    }
+                    "PD" => {
+                        pen_is_down = true;
+                        if params.is_empty() {
+                            ops.push(PlotOp::PenDown);
+                        }
+                    }
+                    "PA" => relative = false,
+                    _ => relative = true,
+                }
+                for pair in params.chunks(2).filter(|pair| pair.len() == 2) {
+                    if relative {
+                        x += pair[0];
+                        y += pair[1];
+                    } else {
+                        x = pair[0];
+                        y = pair[1];
+                    }
+                    if pen_is_down {
+                        ops.push(PlotOp::Draw(x * MM_PER_UNIT, y * MM_PER_UNIT));
+                    } else {
+                        ops.push(PlotOp::MoveTo(x * MM_PER_UNIT, y * MM_PER_UNIT));
+                    }
+                }
+            }
+            _ => warn_once(&mut warned, &mnemonic),
+        }
+    }
+    ops
+}
+
+/// Print a warning about an unsupported command, the first time it is seen.
+fn warn_once(warned: &mut Vec<String>, mnemonic: &str) {
+    if !warned.iter().any(|w| w == mnemonic) {
+        eprintln!("Skipping unsupported HPGL command {}.", mnemonic);
+        warned.push(mnemonic.to_string());
+    }
+}
@@ -0,0 +1,77 @@
+//! The lsystem module contains an L-system interpreter that drives the plotter like a turtle.
+//! https://en.wikipedia.org/wiki/L-system
+//!
+//! Symbols understood when drawing:
+//! F  draw forward one step      f  move forward one step without drawing
+//! +  turn left (CCW)            -  turn right (CW)
+//! [  save position and heading  ]  return to the last saved position and heading
+//! Other symbols are only used during expansion.
+//!
+
+use crate::plottable::Plottable;
+
+const MAX_SYMBOLS: usize = 5_000_000; // Stop expanding past this, before memory runs out.
+
+/// Expand the axiom by applying the rules `iterations` times, then draw the result.
+///
+/// plotter: device to plot to.
+/// axiom: starting string.
+/// rules: replacement for each symbol; symbols without a rule are copied unchanged.
+/// step_mm: distance moved by F and f.
+/// turn_rad: angle turned by + and -.
+/// start: starting position in mm.
+/// start_heading_rad: starting direction (0 = to the right, PI/2 = up).
+///
+/// # Examples
+///
+/// ```
+/// // Koch snowflake.
+/// plot_lsystem(&mut plotter, "F--F--F", &[('F', "F+F--F+F")], 3, 2.0, PI / 3.0, (-27.0, 15.0), 0.0);
+/// ```
+///
+pub fn plot_lsystem(plotter: &mut impl Plottable, axiom: &str, rules: &[(char, &str)], iterations: u32,
+                    step_mm: f64, turn_rad: f64, start: (f64, f64), start_heading_rad: f64) {
+    let symbols = expand(axiom, rules, iterations);
+
+    let mut pos = start;
+    let mut heading = start_heading_rad;
+    let mut stack: Vec<((f64, f64), f64)> = Vec::new();
+    plotter.move_to(pos.0, pos.1);
+    for c in symbols.chars() {
+        match c {
+            'F' => pos = plotter.draw_relative(step_mm * heading.cos(), step_mm * heading.sin()),
+            'f' => pos = plotter.move_relative(step_mm * heading.cos(), step_mm * heading.sin()),
+            '+' => heading += turn_rad,
+            '-' => heading -= turn_rad,
+            '[' => stack.push((pos, heading)),
+            ']' => {
+                if let Some((saved_pos, saved_heading)) = stack.pop() {
+                    pos = saved_pos;
+                    heading = saved_heading;
+                    plotter.move_to(pos.0, pos.1);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Apply the rules to the axiom `iterations` times.
+pub fn expand(axiom: &str, rules: &[(char, &str)], iterations: u32) -> String {
+    let mut current = axiom.to_string();
+    for i in 0 .. iterations {
+        let mut next = String::with_capacity(current.len() * 2);
+        for c in current.chars() {
+            match rules.iter().find(|&&(symbol, _)| symbol == c) {
+                Some(&(_, replacement)) => next.push_str(replacement),
+                None => next.push(c),
+            }
+        }
+        if next.len() > MAX_SYMBOLS {
+            eprintln!("L-system too long after {} iterations, stopping at {}.", i + 1, i);
+            break;
+        }
+        current = next;
+    }
+    current
+}
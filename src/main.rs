@@ -1,66 +1,166 @@
 //! Crate to create interesting plots with a USCutter LPII cutter/plotter, including
 //! a method to preview the operations using turtle graphics.
 //!
-//! To use: edit the beginning of main() to set the window size and whether you want
-//! to display on the screen or send to a plotter.
-//! Then put the code to create the plot into generate_plot().
+//! To use: put the code to create the plot into generate_plot(), then run with `--preview`
+//! (the default) to display it on the screen, or `--plotter COM12` to send it to a plotter.
+//! `--bounds minx,miny,maxx,maxy` sets the plot rectangle in mm, and `--preview --export plot.svg`
+//! also saves the preview as an SVG file; `--help` lists the options.
 //!
 
+mod ascii_plot;
+mod attractor;
+mod charts;
+mod curves;
+mod dither;
+mod fill;
+mod gcode_plot;
+mod geometry;
+mod hpgl;
+mod lsystem;
+mod maze;
+mod pipeline;
+mod plot_bounds;
+mod plot_builder;
+mod plot_file;
 mod plottable;
+mod rng;
 mod roulette;
+mod scaled_plot;
+mod script;
+mod shapes;
+mod svg_plot;
+mod symmetric_plot;
+#[cfg(test)]
+mod testing;
+mod text;
+mod tiling;
 mod turtle_plot; // Load the modules from files of the same name.
 mod uscutter;
+mod voronoi;
 
 use std::f64::consts::PI;
+use std::env;
 use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process;
 use plottable::Plottable;
-use uscutter::USCutter;
+use uscutter::{USCutter, USCutterError};
 use crate::turtle_plot::TurtlePlotter;
 use roulette::full_hypotrochoid;
 
-fn main()  -> Result<(), Box<dyn Error>> {
-    // Choose whether to display on screen or send to plotter.
-    // TODO: add code to read the command line to get this value.
-    let send_to_plotter = true;
+const DEFAULT_PORT: &str = "COM12"; // Plotter through the docking station.  ("COM4" is the FTDI cable.)
+const DEFAULT_BOUNDS: (f64, f64, f64, f64) = (-40.0, -40.0, 40.0, 40.0); // llx, lly, urx, ury in mm.
+const USAGE: &str = "Usage: rplotter [--preview [--export FILE] | --plotter [PORT]] [--bounds MINX,MINY,MAXX,MAXY]
+
+  --preview          Draw in a turtle graphics window (the default).
+  --export FILE      With --preview, also save the drawing as SVG to FILE when it is finished.
+  --plotter [PORT]   Send the plot to the cutter/plotter on PORT (default COM12).
+  --bounds ...       Plot rectangle in mm (default -40,-40,40,40).
+  --help             Print this message.";
+
+/// Settings read from the command line.
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    port_name: Option<String>,      // Cutter/plotter port, or None for the on-screen preview.
+    export_path: Option<PathBuf>,   // SVG file to save the preview to, if any.
+    bounds: (f64, f64, f64, f64),   // Plot rectangle, (llx_mm, lly_mm, urx_mm, ury_mm).
+}
 
-    // Plot bounds, lower left corner
-    // Change these when setting up a plot.
-    let plot_minx_mm = -40.0;
-    let plot_miny_mm = -40.0;
-    // Upper right corner
-    let plot_maxx_mm = 40.0;
-    let plot_maxy_mm = 40.0;
+fn main()  -> Result<(), Box<dyn Error>> {
+    // Choose the output device and plot bounds from the command line.  With no arguments, preview
+    // on screen, so the plotter is only driven when asked for.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("Error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
 
     // Below here in this function should not have to change as plots are changed.
 
     // Choose which output device we are using.
-    // Note that we might be able to use trait objects to create a generic plotter variable/struct that can hold either type of device
-    // and thus move the "initialize, generate, finalize" parts of the code outside the "if" expression.  But that is more digging
-    // than I want to do at the moment; the approach below works.  See these parts of the Rust Book:
-    // https://doc.rust-lang.org/book/ch17-02-trait-objects.html#using-trait-objects-that-allow-for-values-of-different-types
-    // https://doc.rust-lang.org/book/ch19-04-advanced-types.html#dynamically-sized-types-and-the-sized-trait
-    if send_to_plotter {
+    let mut plotter = make_plotter(options.port_name.as_deref(), options.export_path.as_deref(), options.bounds)?;
+    plotter.initialize();
+    generate_plot(&mut plotter);
+    plotter.check_status()?; // Already finalized if the plot ran out of time.
+    plotter.finalize();
+
+    Ok(())
+}
+
+/// Read the options from the command line arguments, not including the program name.
+/// Returns a description of the problem if an argument isn't understood.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { port_name: None, export_path: None, bounds: DEFAULT_BOUNDS };
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--preview" => options.port_name = None,
+            "--plotter" => {
+                // The port is optional; the next argument is only taken if it isn't another option.
+                match args.get(i + 1).filter(|arg| !arg.starts_with("--")) {
+                    Some(port) => {
+                        options.port_name = Some(port.clone());
+                        i += 1;
+                    }
+                    None => options.port_name = Some(DEFAULT_PORT.to_string()),
+                }
+            }
+            "--bounds" => {
+                let text = args.get(i + 1).ok_or("--bounds needs MINX,MINY,MAXX,MAXY")?;
+                let values = text.split(',')
+                    .map(|v| v.trim().parse::<f64>())
+                    .collect::<Result<Vec<f64>, _>>()
+                    .map_err(|_| format!("bounds \"{}\" must be four numbers", text))?;
+                if values.len() != 4 {
+                    return Err(format!("bounds \"{}\" must be four numbers", text));
+                }
+                if values[2] <= values[0] || values[3] <= values[1] {
+                    return Err(format!("bounds \"{}\": MAXX and MAXY must be greater than MINX and MINY", text));
+                }
+                options.bounds = (values[0], values[1], values[2], values[3]);
+                i += 1;
+            }
+            "--export" => {
+                let path = args.get(i + 1).filter(|arg| !arg.starts_with("--")).ok_or("--export needs a FILE")?;
+                options.export_path = Some(PathBuf::from(path));
+                i += 1;
+            }
+            other => return Err(format!("unknown argument \"{}\"", other)),
+        }
+        i += 1;
+    }
+    if options.port_name.is_some() && options.export_path.is_some() {
+        return Err("--export only works with --preview".to_string());
+    }
+    Ok(options)
+}
+
+/// Create the output device: the cutter/plotter on port_name if given, otherwise a turtle
+/// graphics window, which is also saved as SVG to export_path if given.
+/// bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+/// Returns an error if the cutter/plotter can't be opened.
+fn make_plotter(port_name: Option<&str>, export_path: Option<&Path>, bounds: (f64, f64, f64, f64))
+                -> Result<Box<dyn Plottable>, USCutterError> {
+    let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+    match port_name {
         // Cutter/plotter.
-//    let port_name = "COM4";  // FTDI cable through the docking station.
-        let port_name = "COM12"; // Plotter through the docking station.
-        let mut plotter = USCutter::new(port_name, plot_minx_mm, plot_miny_mm, plot_maxx_mm, plot_maxy_mm);
-        plotter.initialize();
-        generate_plot(&mut plotter);
-        plotter.finalize();
-    } else {
+        Some(port_name) => Ok(Box::new(USCutter::new(port_name, llx_mm, lly_mm, urx_mm, ury_mm)?)),
         // Turtle graphics plotting
-        let mut plotter = TurtlePlotter::new(plot_minx_mm, plot_miny_mm, plot_maxx_mm, plot_maxy_mm);
-        // Code below is duplicated because plotter lives only within the else block, and I haven't figured out how to make a generic for it.
-        plotter.initialize();
-        generate_plot(&mut plotter);
-        plotter.finalize();
+        None => Ok(Box::new(TurtlePlotter::new_with_export(llx_mm, lly_mm, urx_mm, ury_mm,
+                                                           export_path.map(Path::to_path_buf)))),
     }
-
-    Ok(())
 }
 
 /// Function to hold plot-generating commands (outside of initialize() and finalize() ).
 /// Call the plotter's move_to() and draw() methods, or write other functions that do.
+/// To keep a copy of the preview, run with `--preview --export plot.svg`.
 fn generate_plot(plotter: &mut impl Plottable) {
     // Put plot-generating commands here.
     let colors = ["black", "blue", "green", "yellow"];
@@ -0,0 +1,105 @@
+//! The maze module contains code to generate and draw perfect mazes: every cell can be reached
+//! from every other by exactly one path.
+//! https://en.wikipedia.org/wiki/Maze_generation_algorithm#Randomized_depth-first_search
+//!
+
+use crate::plottable::Plottable;
+use crate::rng::Rng;
+
+/// Generate a maze by randomized depth-first search (the recursive backtracker) and draw its walls.
+/// There is an entrance in the bottom wall of the lower left cell and an exit in the top wall of
+/// the upper right cell.
+///
+/// plotter: device to plot to.
+/// cols, rows: size of the maze in cells.
+/// cell_mm: width of each cell (and so of the corridors).
+/// llx_mm, lly_mm: lower left corner of the maze.
+/// seed: seed for the random choices.  The same seed gives the same maze.
+///
+/// Walls along each grid line are joined into runs and drawn with one stroke per run,
+/// alternating direction from line to line, so no wall is drawn twice and the pen moves little.
+///
+pub fn maze(plotter: &mut impl Plottable, cols: u32, rows: u32, cell_mm: f64, llx_mm: f64, lly_mm: f64, seed: u64) {
+    // Error checking.
+    if cols == 0 || rows == 0 {
+        panic!("Parameters `cols` and `rows` must be at least 1.")
+    }
+    let (cols, rows) = (cols as usize, rows as usize);
+    let mut rng = Rng::new(seed);
+
+    // Walls to the right of and above each cell, indexed [row][col].
+    let mut right = vec![vec![true; cols]; rows];
+    let mut top = vec![vec![true; cols]; rows];
+    let mut visited = vec![vec![false; cols]; rows];
+
+    // Carve passages: walk to a random unvisited neighbor, backing up when there is none.
+    let mut stack = vec![(0, 0)];
+    visited[0][0] = true;
+    while let Some(&(r, c)) = stack.last() {
+        let mut neighbors = Vec::with_capacity(4);
+        if r > 0 && !visited[r - 1][c] { neighbors.push((r - 1, c)); }
+        if r + 1 < rows && !visited[r + 1][c] { neighbors.push((r + 1, c)); }
+        if c > 0 && !visited[r][c - 1] { neighbors.push((r, c - 1)); }
+        if c + 1 < cols && !visited[r][c + 1] { neighbors.push((r, c + 1)); }
+        if neighbors.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let (nr, nc) = neighbors[rng.below(neighbors.len() as u32) as usize];
+        match (nr as i64 - r as i64, nc as i64 - c as i64) {
+            (1, _) => top[r][c] = false,
+            (-1, _) => top[nr][c] = false,
+            (_, 1) => right[r][c] = false,
+            _ => right[r][nc] = false,
+        }
+        visited[nr][nc] = true;
+        stack.push((nr, nc));
+    }
+
+    // Horizontal grid lines, bottom to top.  Line k runs along the bottom of row k.
+    let mut forward = true;
+    for k in 0 .. (rows + 1) {
+        let wall = |c: usize| match k {
+            0 => c != 0,                     // Entrance.
+            _ if k == rows => c != cols - 1, // Exit.
+            _ => top[k - 1][c],
+        };
+        let y = lly_mm + k as f64 * cell_mm;
+        draw_runs(plotter, cols, wall, forward, |c| (llx_mm + c as f64 * cell_mm, y));
+        forward = !forward;
+    }
+    // Vertical grid lines, left to right.  Line k runs along the left of column k.
+    for k in 0 .. (cols + 1) {
+        let wall = |r: usize| k == 0 || k == cols || right[r][k - 1];
+        let x = llx_mm + k as f64 * cell_mm;
+        draw_runs(plotter, rows, wall, forward, |r| (x, lly_mm + r as f64 * cell_mm));
+        forward = !forward;
+    }
+}
+
+/// Draw the runs of consecutive walls along one grid line of len cells.  wall(i) says whether
+/// cell i's stretch of the line has a wall, and point(i) is where that stretch starts.
+fn draw_runs<W, P>(plotter: &mut impl Plottable, len: usize, wall: W, forward: bool, point: P)
+    where W: Fn(usize) -> bool, P: Fn(usize) -> (f64, f64) {
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < len {
+        if wall(i) {
+            let start = i;
+            while i < len && wall(i) {
+                i += 1;
+            }
+            runs.push((start, i));
+        } else {
+            i += 1;
+        }
+    }
+    if !forward {
+        runs.reverse();
+    }
+    for (start, end) in runs {
+        let (from, to) = if forward { (point(start), point(end)) } else { (point(end), point(start)) };
+        plotter.move_to(from.0, from.1);
+        plotter.draw(to.0, to.1);
+    }
+}
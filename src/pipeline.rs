@@ -0,0 +1,745 @@
+//! pipeline module contains wrappers that sit between plot-generating code and an output
+//! device, each one a Plottable that transforms or measures what passes through it, and the
+//! PlotterBuilder that chains them together in front of a device.
+//!
+//! # Examples
+//!
+//! ```
+//! let mut plotter = PlotterBuilder::new()
+//!     .with_fit_to_page()
+//!     .with_simplify(0.2)
+//!     .with_metering()
+//!     .to_cutter("COM12", (-40.0, -40.0, 40.0, 40.0))?;
+//! plotter.initialize();
+//! generate_plot(&mut plotter);
+//! plotter.finalize();
+//! ```
+//!
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::gcode_plot::{GcodePlotter, GcodeSettings};
+use crate::geometry::{optimize_travel, simplify_path};
+use crate::plot_builder::{replay, PlotBuilder, PlotOp};
+use crate::plottable::Plottable;
+use crate::svg_plot::SvgPlotter;
+use crate::turtle_plot::TurtlePlotter;
+use crate::uscutter::{USCutter, USCutterError};
+
+/// Records the whole plot, then on finalize() scales and centers it to fill the wrapped
+/// plotter's plot rectangle, keeping its proportions.
+pub struct FitToPagePlotter<P: Plottable> {
+    inner: P,
+    recorder: PlotBuilder,
+}
+
+impl<P: Plottable> FitToPagePlotter<P> {
+    pub fn new(inner: P) -> FitToPagePlotter<P> {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = inner.bounds();
+        FitToPagePlotter { inner, recorder: PlotBuilder::new(llx_mm, lly_mm, urx_mm, ury_mm) }
+    }
+}
+
+impl<P: Plottable> Plottable for FitToPagePlotter<P> {
+    fn initialize(&mut self) {
+        self.inner.initialize();
+    }
+
+    /// Sends the whole recorded plot, fitted to the page, then finalizes the wrapped plotter.
+    fn finalize(&mut self) {
+        let ops = self.recorder.ops();
+        let points = ops.iter().filter_map(|op| match op {
+            PlotOp::MoveTo(x, y) | PlotOp::Draw(x, y) => Some((*x, *y)),
+            _ => None,
+        });
+        let (mut minx, mut miny) = (f64::INFINITY, f64::INFINITY);
+        let (mut maxx, mut maxy) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (x, y) in points {
+            minx = minx.min(x);
+            miny = miny.min(y);
+            maxx = maxx.max(x);
+            maxy = maxy.max(y);
+        }
+        if minx <= maxx {
+            let (llx, lly, urx, ury) = self.inner.bounds();
+            // Largest scale that fits both ways.  A zero-width or zero-height design only constrains the other way.
+            let sx = if maxx > minx { (urx - llx) / (maxx - minx) } else { f64::INFINITY };
+            let sy = if maxy > miny { (ury - lly) / (maxy - miny) } else { f64::INFINITY };
+            let scale = if sx.min(sy).is_finite() { sx.min(sy) } else { 1.0 };
+            let fit = |x: f64, y: f64| ((llx + urx) / 2.0 + scale * (x - (minx + maxx) / 2.0),
+                                        (lly + ury) / 2.0 + scale * (y - (miny + maxy) / 2.0));
+            let fitted: Vec<PlotOp> = ops.iter().map(|op| match op {
+                PlotOp::MoveTo(x, y) => { let (x, y) = fit(*x, *y); PlotOp::MoveTo(x, y) }
+                PlotOp::Draw(x, y) => { let (x, y) = fit(*x, *y); PlotOp::Draw(x, y) }
+                other => other.clone(),
+            }).collect();
+            replay(&fitted, &mut self.inner);
+        } else {
+            replay(ops, &mut self.inner);
+        }
+        self.inner.finalize();
+    }
+
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.recorder.draw(destx_mm, desty_mm);
+    }
+
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.recorder.move_to(destx_mm, desty_mm);
+    }
+
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.recorder.draw_relative(dx_mm, dy_mm)
+    }
+
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.recorder.move_relative(dx_mm, dy_mm)
+    }
+
+    fn pen_up(&mut self) {
+        self.recorder.pen_up();
+    }
+
+    fn pen_down(&mut self) {
+        self.recorder.pen_down();
+    }
+
+    fn change_color(&mut self, color_name: &str) {
+        self.recorder.change_color(color_name);
+    }
+
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        self.inner.bounds()
+    }
+
+    fn position(&self) -> (f64, f64) {
+        self.recorder.position()
+    }
+
+    fn check_status(&self) -> io::Result<()> {
+        self.inner.check_status()
+    }
+
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.recorder.set_velocity(cm_per_sec);
+    }
+
+    /// Only the wrapped plotter is flushed; the recording can't be sent until finalize().
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+}
+
+/// Collects each pen-down path and simplifies it before passing it on, dropping points that
+/// deviate less than the tolerance from a straight line.  See geometry::simplify_path().
+pub struct SimplifyPlotter<P: Plottable> {
+    inner: P,
+    tolerance_mm: f64,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    path: Vec<(f64, f64)>, // Pen-down path in progress, starting at the last move.
+}
+
+impl<P: Plottable> SimplifyPlotter<P> {
+    pub fn new(inner: P, tolerance_mm: f64) -> SimplifyPlotter<P> {
+        let (llx_mm, lly_mm, _, _) = inner.bounds();
+        SimplifyPlotter { inner, tolerance_mm, pos_x_mm: llx_mm, pos_y_mm: lly_mm, path: Vec::new() }
+    }
+
+    /// Send the path in progress, simplified.
+    fn send_path(&mut self) {
+        if self.path.len() > 1 {
+            let path = simplify_path(&self.path, self.tolerance_mm);
+            self.inner.move_to(path[0].0, path[0].1);
+            for &(x, y) in path[1 ..].iter() {
+                self.inner.draw(x, y);
+            }
+        }
+        self.path.clear();
+    }
+}
+
+impl<P: Plottable> Plottable for SimplifyPlotter<P> {
+    fn initialize(&mut self) {
+        self.inner.initialize();
+    }
+
+    fn finalize(&mut self) {
+        self.send_path();
+        self.inner.finalize();
+    }
+
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        if self.path.is_empty() {
+            self.path.push((self.pos_x_mm, self.pos_y_mm));
+        }
+        self.path.push((destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.send_path();
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn pen_up(&mut self) {
+        self.send_path();
+        self.inner.pen_up();
+    }
+
+    fn pen_down(&mut self) {
+        self.send_path();
+        self.inner.move_to(self.pos_x_mm, self.pos_y_mm);
+        self.inner.pen_down();
+    }
+
+    fn change_color(&mut self, color_name: &str) {
+        self.send_path();
+        self.inner.change_color(color_name);
+    }
+
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        self.inner.bounds()
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn check_status(&self) -> io::Result<()> {
+        self.inner.check_status()
+    }
+
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.send_path();
+        self.inner.set_velocity(cm_per_sec);
+    }
+
+    fn home(&mut self) {
+        self.send_path();
+        let (llx_mm, lly_mm, _, _) = self.inner.bounds();
+        self.pos_x_mm = llx_mm; // Update position.
+        self.pos_y_mm = lly_mm;
+        self.inner.home();
+    }
+
+    /// Sends the path in progress, simplified on its own, then flushes the wrapped plotter.
+    fn flush(&mut self) {
+        self.send_path();
+        self.inner.flush();
+    }
+}
+
+/// Collects the pen-down paths of each color, then sends them in the order that keeps pen-up
+/// travel short, when the color or speed changes and at finalize().  See geometry::optimize_travel().
+/// Paths are drawn whole but may be reversed, so use this only where drawing order and
+/// direction don't matter, e.g. not in front of overlapping fills that rely on drawing order.
+pub struct TravelPlotter<P: Plottable> {
+    inner: P,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    path: Vec<(f64, f64)>,       // Pen-down path in progress, starting at the last move.
+    paths: Vec<Vec<(f64, f64)>>, // Finished paths in the present color.
+}
+
+impl<P: Plottable> TravelPlotter<P> {
+    pub fn new(inner: P) -> TravelPlotter<P> {
+        let (llx_mm, lly_mm, _, _) = inner.bounds();
+        TravelPlotter { inner, pos_x_mm: llx_mm, pos_y_mm: lly_mm, path: Vec::new(), paths: Vec::new() }
+    }
+
+    /// Finish the path in progress.
+    fn end_path(&mut self) {
+        if self.path.len() > 1 {
+            self.paths.push(std::mem::replace(&mut self.path, Vec::new()));
+        }
+        self.path.clear();
+    }
+
+    /// Send the collected paths, reordered.
+    fn send_paths(&mut self) {
+        self.end_path();
+        let paths = std::mem::replace(&mut self.paths, Vec::new());
+        for path in optimize_travel(paths) {
+            self.inner.move_to(path[0].0, path[0].1);
+            for &(x, y) in path[1 ..].iter() {
+                self.inner.draw(x, y);
+            }
+        }
+    }
+}
+
+impl<P: Plottable> Plottable for TravelPlotter<P> {
+    fn initialize(&mut self) {
+        self.inner.initialize();
+    }
+
+    fn finalize(&mut self) {
+        self.send_paths();
+        self.inner.finalize();
+    }
+
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        if self.path.is_empty() {
+            self.path.push((self.pos_x_mm, self.pos_y_mm));
+        }
+        self.path.push((destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.end_path();
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn pen_up(&mut self) {
+        self.end_path();
+    }
+
+    /// Kept as a path of no length, so it is reordered with the rest and drawn as a dot.
+    fn pen_down(&mut self) {
+        self.end_path();
+        let here = (self.pos_x_mm, self.pos_y_mm);
+        self.paths.push(vec![here, here]);
+    }
+
+    fn change_color(&mut self, color_name: &str) {
+        self.send_paths();
+        self.inner.change_color(color_name);
+    }
+
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        self.inner.bounds()
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn check_status(&self) -> io::Result<()> {
+        self.inner.check_status()
+    }
+
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.send_paths();
+        self.inner.set_velocity(cm_per_sec);
+    }
+
+    fn home(&mut self) {
+        self.send_paths();
+        let (llx_mm, lly_mm, _, _) = self.inner.bounds();
+        self.pos_x_mm = llx_mm; // Update position.
+        self.pos_y_mm = lly_mm;
+        self.inner.home();
+    }
+
+    /// Sends the paths collected so far, reordered among themselves, then flushes the wrapped plotter.
+    fn flush(&mut self) {
+        self.send_paths();
+        self.inner.flush();
+    }
+}
+
+// Rough plotter speeds, for estimating plot time.
+const DRAW_MM_PER_S: f64 = 50.0;     // Pen down.
+const MOVE_MM_PER_S: f64 = 150.0;    // Pen up.
+const COLOR_CHANGE_S: f64 = 30.0;    // Time to swap a pen by hand.
+
+/// Summary of a plot: how many operations, how far the pen travels, and how long it might take.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotStats {
+    pub draws: u32,
+    pub moves: u32,
+    pub color_changes: u32,
+    pub pen_down_mm: f64, // Total distance drawn.
+    pub pen_up_mm: f64,   // Total distance moved without drawing.
+    /// Extent of everything drawn, as (llx_mm, lly_mm, urx_mm, ury_mm), or None if nothing was drawn.
+    pub bounding_box: Option<(f64, f64, f64, f64)>,
+}
+
+impl PlotStats {
+    pub fn new() -> PlotStats {
+        PlotStats { draws: 0, moves: 0, color_changes: 0, pen_down_mm: 0.0, pen_up_mm: 0.0, bounding_box: None }
+    }
+
+    /// Statistics for recorded operations, with the pen starting at start.
+    pub fn from_ops(ops: &[PlotOp], start: (f64, f64)) -> PlotStats {
+        let mut stats = PlotStats::new();
+        let mut pos = start;
+        for op in ops {
+            match op {
+                PlotOp::Draw(x, y) => {
+                    stats.record_draw(pos, (*x, *y));
+                    pos = (*x, *y);
+                }
+                PlotOp::MoveTo(x, y) => {
+                    stats.record_move(pos, (*x, *y));
+                    pos = (*x, *y);
+                }
+                PlotOp::ChangeColor(_) => stats.color_changes += 1,
+                PlotOp::PenUp | PlotOp::PenDown | PlotOp::SetVelocity(_) => {}
+            }
+        }
+        stats
+    }
+
+    /// Estimated plot time in seconds, from the distances, typical speeds, and manual pen changes.
+    pub fn estimated_seconds(&self) -> f64 {
+        self.pen_down_mm / DRAW_MM_PER_S + self.pen_up_mm / MOVE_MM_PER_S + self.color_changes as f64 * COLOR_CHANGE_S
+    }
+
+    fn record_draw(&mut self, from: (f64, f64), to: (f64, f64)) {
+        self.draws += 1;
+        self.pen_down_mm += distance(from, to);
+        for &(x, y) in [from, to].iter() {
+            self.bounding_box = Some(match self.bounding_box {
+                None => (x, y, x, y),
+                Some((llx, lly, urx, ury)) => (llx.min(x), lly.min(y), urx.max(x), ury.max(y)),
+            });
+        }
+    }
+
+    fn record_move(&mut self, from: (f64, f64), to: (f64, f64)) {
+        self.moves += 1;
+        self.pen_up_mm += distance(from, to);
+    }
+}
+
+impl fmt::Display for PlotStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Pen down: {} draws, {:.1} mm", self.draws, self.pen_down_mm)?;
+        writeln!(f, "Pen up:   {} moves, {:.1} mm", self.moves, self.pen_up_mm)?;
+        writeln!(f, "Color changes: {}", self.color_changes)?;
+        match self.bounding_box {
+            Some((llx, lly, urx, ury)) => writeln!(f, "Drawn area: ({:.1}, {:.1}) to ({:.1}, {:.1}) mm", llx, lly, urx, ury)?,
+            None => writeln!(f, "Drawn area: nothing drawn")?,
+        }
+        let seconds = self.estimated_seconds().round() as u64;
+        write!(f, "Estimated time: {}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+/// Passes everything through unchanged while collecting PlotStats,
+/// and prints them on finalize().
+pub struct MeteringPlotter<P: Plottable> {
+    inner: P,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    stats: PlotStats,
+}
+
+impl<P: Plottable> MeteringPlotter<P> {
+    pub fn new(inner: P) -> MeteringPlotter<P> {
+        let (llx_mm, lly_mm, _, _) = inner.bounds();
+        MeteringPlotter { inner, pos_x_mm: llx_mm, pos_y_mm: lly_mm, stats: PlotStats::new() }
+    }
+
+    /// Statistics for everything plotted so far.
+    pub fn stats(&self) -> &PlotStats {
+        &self.stats
+    }
+}
+
+impl<P: Plottable> Plottable for MeteringPlotter<P> {
+    fn initialize(&mut self) {
+        self.inner.initialize();
+    }
+
+    fn finalize(&mut self) {
+        self.inner.finalize();
+        println!("{}", self.stats);
+    }
+
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.stats.record_draw((self.pos_x_mm, self.pos_y_mm), (destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+        self.inner.draw(destx_mm, desty_mm);
+    }
+
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.stats.record_move((self.pos_x_mm, self.pos_y_mm), (destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+        self.inner.move_to(destx_mm, desty_mm);
+    }
+
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn pen_up(&mut self) {
+        self.inner.pen_up();
+    }
+
+    fn pen_down(&mut self) {
+        self.inner.pen_down();
+    }
+
+    fn change_color(&mut self, color_name: &str) {
+        self.stats.color_changes += 1;
+        self.inner.change_color(color_name);
+    }
+
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        self.inner.bounds()
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn check_status(&self) -> io::Result<()> {
+        self.inner.check_status()
+    }
+
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.inner.set_velocity(cm_per_sec);
+    }
+
+    fn home(&mut self) {
+        let (llx_mm, lly_mm, _, _) = self.inner.bounds();
+        self.stats.record_move((self.pos_x_mm, self.pos_y_mm), (llx_mm, lly_mm));
+        self.pos_x_mm = llx_mm; // Update position.
+        self.pos_y_mm = lly_mm;
+        self.inner.home();
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+}
+
+/// Applies a distortion function to every coordinate before passing it on, for bulge, wave
+/// or noise effects on any design.  Lines are cut into pieces no longer than segment_mm before
+/// being warped, so straight lines bend with the warp instead of cutting across it.
+///
+/// # Examples
+///
+/// ```
+/// // Horizontal ripple.
+/// let mut warped = WarpPlotter::new(&mut plotter, |(x, y): (f64, f64)| (x, y + 2.0 * (x / 5.0).sin()), 0.5);
+/// ```
+///
+pub struct WarpPlotter<P: Plottable, F: Fn((f64, f64)) -> (f64, f64)> {
+    inner: P,
+    warp: F,
+    segment_mm: f64, // Longest line sent unsplit.
+    pos_x_mm: f64,   // Present position of the pen in mm, before warping.
+    pos_y_mm: f64,
+}
+
+impl<P: Plottable, F: Fn((f64, f64)) -> (f64, f64)> WarpPlotter<P, F> {
+    pub fn new(inner: P, warp: F, segment_mm: f64) -> WarpPlotter<P, F> {
+        if segment_mm <= 0.0 {
+            panic!("Parameter `segment_mm` must be greater than zero.")
+        }
+        let (llx_mm, lly_mm, _, _) = inner.bounds();
+        WarpPlotter { inner, warp, segment_mm, pos_x_mm: llx_mm, pos_y_mm: lly_mm }
+    }
+}
+
+impl<P: Plottable, F: Fn((f64, f64)) -> (f64, f64)> Plottable for WarpPlotter<P, F> {
+    fn initialize(&mut self) {
+        self.inner.initialize();
+    }
+
+    fn finalize(&mut self) {
+        self.inner.finalize();
+    }
+
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        let (dx, dy) = (destx_mm - self.pos_x_mm, desty_mm - self.pos_y_mm);
+        let pieces = (((dx * dx + dy * dy).sqrt() / self.segment_mm).ceil() as i32).max(1);
+        for i in 1 .. (pieces + 1) {
+            let t = i as f64 / pieces as f64;
+            let (x, y) = (self.warp)((self.pos_x_mm + t * dx, self.pos_y_mm + t * dy));
+            self.inner.draw(x, y);
+        }
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        let (x, y) = (self.warp)((destx_mm, desty_mm));
+        self.inner.move_to(x, y);
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn pen_up(&mut self) {
+        self.inner.pen_up();
+    }
+
+    fn pen_down(&mut self) {
+        self.inner.pen_down();
+    }
+
+    fn change_color(&mut self, color_name: &str) {
+        self.inner.change_color(color_name);
+    }
+
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        self.inner.bounds()
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn check_status(&self) -> io::Result<()> {
+        self.inner.check_status()
+    }
+
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.inner.set_velocity(cm_per_sec);
+    }
+
+    fn home(&mut self) {
+        let (llx_mm, lly_mm, _, _) = self.inner.bounds();
+        self.pos_x_mm = llx_mm; // Update position.
+        self.pos_y_mm = lly_mm;
+        self.inner.home();
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+}
+
+/// One stage of a pipeline.
+enum Stage {
+    FitToPage,
+    Simplify(f64),
+    Travel,
+    Metering,
+}
+
+/// Assembles an output pipeline: the stages run in the order they are added,
+/// and the last one feeds the device chosen with one of the to_...() methods.
+pub struct PlotterBuilder {
+    stages: Vec<Stage>,
+}
+
+impl PlotterBuilder {
+    pub fn new() -> PlotterBuilder {
+        PlotterBuilder { stages: Vec::new() }
+    }
+
+    /// Add a FitToPagePlotter stage.
+    pub fn with_fit_to_page(mut self) -> PlotterBuilder {
+        self.stages.push(Stage::FitToPage);
+        self
+    }
+
+    /// Add a SimplifyPlotter stage with the given tolerance.
+    pub fn with_simplify(mut self, tolerance_mm: f64) -> PlotterBuilder {
+        self.stages.push(Stage::Simplify(tolerance_mm));
+        self
+    }
+
+    /// Add a TravelPlotter stage, reordering paths to shorten pen-up travel.
+    pub fn with_travel_optimization(mut self) -> PlotterBuilder {
+        self.stages.push(Stage::Travel);
+        self
+    }
+
+    /// Add a MeteringPlotter stage.
+    pub fn with_metering(mut self) -> PlotterBuilder {
+        self.stages.push(Stage::Metering);
+        self
+    }
+
+    /// Finish the pipeline with a USCutter on port_name.  bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+    /// Returns an error if the USCutter can't be created.  See USCutter::new().
+    pub fn to_cutter(self, port_name: &str, bounds: (f64, f64, f64, f64)) -> Result<Box<dyn Plottable>, USCutterError> {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+        Ok(self.wrap(Box::new(USCutter::new(port_name, llx_mm, lly_mm, urx_mm, ury_mm)?)))
+    }
+
+    /// Finish the pipeline with an HPGL file.  bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+    /// Returns an error if the file can't be created.  See USCutter::to_file().
+    pub fn to_file(self, path: &Path, bounds: (f64, f64, f64, f64)) -> Result<Box<dyn Plottable>, USCutterError> {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+        Ok(self.wrap(Box::new(USCutter::to_file(path, llx_mm, lly_mm, urx_mm, ury_mm)?)))
+    }
+
+    /// Finish the pipeline with a G-code file for generic plotter firmware.
+    /// bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+    pub fn to_gcode(self, path: &Path, bounds: (f64, f64, f64, f64), settings: GcodeSettings) -> Box<dyn Plottable> {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+        self.wrap(Box::new(GcodePlotter::new(path, llx_mm, lly_mm, urx_mm, ury_mm, settings)))
+    }
+
+    /// Finish the pipeline with an SVG file.  bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+    pub fn to_svg(self, path: &Path, bounds: (f64, f64, f64, f64)) -> Box<dyn Plottable> {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+        self.wrap(Box::new(SvgPlotter::new(path, llx_mm, lly_mm, urx_mm, ury_mm)))
+    }
+
+    /// Finish the pipeline with a turtle graphics window.  bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+    pub fn to_turtle(self, bounds: (f64, f64, f64, f64)) -> Box<dyn Plottable> {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+        self.wrap(Box::new(TurtlePlotter::new(llx_mm, lly_mm, urx_mm, ury_mm)))
+    }
+
+    /// Wrap the device in the stages, last stage innermost.
+    fn wrap(self, device: Box<dyn Plottable>) -> Box<dyn Plottable> {
+        let mut plotter = device;
+        for stage in self.stages.into_iter().rev() {
+            plotter = match stage {
+                Stage::FitToPage => Box::new(FitToPagePlotter::new(plotter)),
+                Stage::Simplify(tolerance_mm) => Box::new(SimplifyPlotter::new(plotter, tolerance_mm)),
+                Stage::Travel => Box::new(TravelPlotter::new(plotter)),
+                Stage::Metering => Box::new(MeteringPlotter::new(plotter)),
+            };
+        }
+        plotter
+    }
+}
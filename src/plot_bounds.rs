@@ -0,0 +1,192 @@
+//! plot_bounds module contains the PlotBounds struct, which implements Plottable by recording
+//! the extent of everything drawn instead of drawing it.  Run a design against a PlotBounds first
+//! to find out whether it will fit the media.
+//!
+
+use crate::plottable::Plottable;
+
+/// An axis-aligned rectangle, in mm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_x_mm: f64,
+    pub min_y_mm: f64,
+    pub max_x_mm: f64,
+    pub max_y_mm: f64,
+}
+
+impl BoundingBox {
+    /// The box holding just the point (x_mm, y_mm).
+    pub fn at(x_mm: f64, y_mm: f64) -> BoundingBox {
+        BoundingBox { min_x_mm: x_mm, min_y_mm: y_mm, max_x_mm: x_mm, max_y_mm: y_mm }
+    }
+
+    /// Grow the box to include (x_mm, y_mm).
+    pub fn include(&mut self, x_mm: f64, y_mm: f64) {
+        self.min_x_mm = self.min_x_mm.min(x_mm);
+        self.min_y_mm = self.min_y_mm.min(y_mm);
+        self.max_x_mm = self.max_x_mm.max(x_mm);
+        self.max_y_mm = self.max_y_mm.max(y_mm);
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x_mm - self.min_x_mm
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y_mm - self.min_y_mm
+    }
+
+    /// True if the box lies within bounds = (llx_mm, lly_mm, urx_mm, ury_mm), edges included.
+    pub fn fits_in(&self, bounds: (f64, f64, f64, f64)) -> bool {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+        self.min_x_mm >= llx_mm && self.min_y_mm >= lly_mm && self.max_x_mm <= urx_mm && self.max_y_mm <= ury_mm
+    }
+
+    /// The box as (llx_mm, lly_mm, urx_mm, ury_mm), the form used for plot rectangles.
+    pub fn as_tuple(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+}
+
+pub struct PlotBounds {
+    min_x_mm: f64, // Plot rectangle, in mm.
+    min_y_mm: f64,
+    max_x_mm: f64,
+    max_y_mm: f64,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    extent: Option<BoundingBox>, // Everything drawn so far, or None if nothing has been.
+}
+
+impl PlotBounds {
+    /// Create a PlotBounds for the plot rectangle with lower left corner (llx_mm, lly_mm) and
+    /// upper right corner (urx_mm, ury_mm).  The rectangle is only reported through bounds(), for
+    /// plot code that sizes itself to it; drawing outside it is recorded, not clipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut measure = PlotBounds::new(-40.0, -40.0, 40.0, 40.0);
+    /// generate_plot(&mut measure);
+    /// if !measure.fits() {
+    ///     println!("Plot overflows the media: {:?}", measure.bounding_box());
+    /// }
+    /// ```
+    ///
+    pub fn new(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> PlotBounds {
+        PlotBounds {
+            min_x_mm: llx_mm,
+            min_y_mm: lly_mm,
+            max_x_mm: urx_mm,
+            max_y_mm: ury_mm,
+            pos_x_mm: llx_mm,
+            pos_y_mm: lly_mm,
+            extent: None,
+        }
+    }
+
+    /// The extent of everything drawn, or None if nothing has been.  Moves with the pen up don't count.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.extent
+    }
+
+    /// True if everything drawn lies within the plot rectangle.
+    pub fn fits(&self) -> bool {
+        self.extent.map_or(true, |extent| extent.fits_in(self.bounds()))
+    }
+
+    fn include(&mut self, x_mm: f64, y_mm: f64) {
+        match self.extent.as_mut() {
+            Some(extent) => extent.include(x_mm, y_mm),
+            None => self.extent = Some(BoundingBox::at(x_mm, y_mm)),
+        }
+    }
+}
+
+impl Plottable for PlotBounds {
+
+    /// Nothing to prepare; provided for compatibility.
+    fn initialize(&mut self) {}
+
+    /// Nothing to finish; provided for compatibility.
+    fn finalize(&mut self) {}
+
+    /// Record a line from present position to absolute position (destx_mm, desty_mm).
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        let (x, y) = (self.pos_x_mm, self.pos_y_mm);
+        self.include(x, y);
+        self.include(destx_mm, desty_mm);
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Move without drawing to absolute position (destx_mm, desty_mm).
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Record a line from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Move without drawing from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Nothing to do; provided for compatibility.
+    fn pen_up(&mut self) {}
+
+    /// Record a dot at the present position.
+    fn pen_down(&mut self) {
+        let (x, y) = (self.pos_x_mm, self.pos_y_mm);
+        self.include(x, y);
+    }
+
+    /// Colors don't affect the extent; provided for compatibility.
+    fn change_color(&mut self, _color_name: &str) {}
+
+    /// The plot rectangle given when the PlotBounds was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roulette;
+
+    #[test]
+    fn hypotrochoid_extent_is_its_plot_radius() {
+        let mut measure = PlotBounds::new(-40.0, -40.0, 40.0, 40.0);
+        roulette::full_hypotrochoid(&mut measure, 30.0, 16.5, 5, 6, 0.0, 0.0, 0.0); // Plot radius 22.5 mm.
+        let extent = measure.bounding_box().unwrap();
+        let r = 22.5 + 1e-9;
+        assert!(extent.fits_in((-r, -r, r, r)), "{:?}", extent);
+        assert!(!extent.fits_in((-22.4, -22.4, 22.4, 22.4)));
+        assert!((extent.width() - 45.0).abs() < 1e-9, "{:?}", extent);
+        assert!(measure.fits());
+    }
+
+    #[test]
+    fn moves_are_not_part_of_the_extent() {
+        let mut measure = PlotBounds::new(0.0, 0.0, 10.0, 10.0);
+        measure.move_to(50.0, 50.0);
+        assert_eq!(measure.bounding_box(), None);
+        measure.move_to(2.0, 3.0);
+        measure.draw(4.0, 8.0);
+        assert_eq!(measure.bounding_box().unwrap().as_tuple(), (2.0, 3.0, 4.0, 8.0));
+    }
+}
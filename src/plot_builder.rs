@@ -0,0 +1,453 @@
+//! plot_builder module contains the PlotBuilder struct, which implements Plottable by recording
+//! the operations instead of driving a device.  Build a design once against a PlotBuilder,
+//! inspect or transform the recorded operations, then replay them to any other Plottable.
+//!
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::geometry::clip_line;
+use crate::plottable::Plottable;
+
+const CHECKPOINT_EVERY: usize = 20; // Operations between writes of the checkpoint file.
+const REGISTRATION_MARK_MM: f64 = 5.0; // Length of each arm of the corner marks on tiled sheets.
+
+/// One recorded plotting operation.  Coordinates are absolute, in mm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlotOp {
+    MoveTo(f64, f64),
+    Draw(f64, f64),
+    PenUp,
+    PenDown,
+    ChangeColor(String),
+    SetVelocity(f64),
+}
+
+pub struct PlotBuilder {
+    min_x_mm: f64, // Plot rectangle, in mm.
+    min_y_mm: f64,
+    max_x_mm: f64,
+    max_y_mm: f64,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    ops: Vec<PlotOp>,
+}
+
+impl PlotBuilder {
+    /// Create an empty PlotBuilder for the plot rectangle with lower left corner (llx_mm, lly_mm)
+    /// and upper right corner (urx_mm, ury_mm).  The rectangle is only reported through bounds();
+    /// nothing is clipped.
+    pub fn new(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> PlotBuilder {
+        PlotBuilder {
+            min_x_mm: llx_mm,
+            min_y_mm: lly_mm,
+            max_x_mm: urx_mm,
+            max_y_mm: ury_mm,
+            pos_x_mm: llx_mm,
+            pos_y_mm: lly_mm,
+            ops: Vec::new(),
+        }
+    }
+
+    /// The operations recorded so far.
+    pub fn ops(&self) -> &[PlotOp] {
+        &self.ops
+    }
+
+    /// Consume the builder, returning the recorded operations.
+    pub fn into_ops(self) -> Vec<PlotOp> {
+        self.ops
+    }
+
+    /// Send the recorded operations to another plotter.  See replay().
+    pub fn replay(&self, plotter: &mut impl Plottable) {
+        replay(&self.ops, plotter);
+    }
+}
+
+/// Send recorded operations to a plotter.  Does not call initialize() or finalize(),
+/// so several recordings can be replayed into one plot.
+pub fn replay(ops: &[PlotOp], plotter: &mut impl Plottable) {
+    for op in ops {
+        match op {
+            PlotOp::MoveTo(x, y) => plotter.move_to(*x, *y),
+            PlotOp::Draw(x, y) => plotter.draw(*x, *y),
+            PlotOp::PenUp => plotter.pen_up(),
+            PlotOp::PenDown => plotter.pen_down(),
+            PlotOp::ChangeColor(color) => plotter.change_color(color),
+            PlotOp::SetVelocity(cm_per_sec) => plotter.set_velocity(*cm_per_sec),
+        }
+    }
+}
+
+/// Send recorded operations to a plotter like replay(), writing the number of operations sent
+/// to the file at checkpoint_path as it goes, so that an interrupted plot can be continued with
+/// resume_from_checkpoint().  The file is removed once every operation has been sent.
+///
+/// Returns the plotter's check_status() error if it stops part way, e.g. a USCutter running out
+/// of time, leaving the file with the count from before it stopped.
+///
+/// The count is written every CHECKPOINT_EVERY operations, so a resumed plot redraws up to that
+/// many operations.  Redrawing a line is harmless; skipping one is not, so the plotter is flushed
+/// before each write, and the count never includes operations still held in a buffer.
+pub fn replay_with_checkpoint(ops: &[PlotOp], plotter: &mut impl Plottable, checkpoint_path: &Path) -> io::Result<()> {
+    replay_from(ops, 0, plotter, checkpoint_path)
+}
+
+/// Continue a replay_with_checkpoint() of the same operations that was interrupted, e.g. by the
+/// program being stopped or the plotter losing power.  Selects the pen color and speed that were
+/// in use, moves with the pen up to where the plot had got to, then sends the rest of the operations,
+/// still checkpointing.  With no checkpoint file, the whole plot is sent.
+///
+/// # Examples
+///
+/// ```
+/// let checkpoint = Path::new("plot.checkpoint");
+/// plotter.initialize();
+/// plot_builder::resume_from_checkpoint(&ops, &mut plotter, checkpoint)?;
+/// plotter.finalize();
+/// ```
+///
+pub fn resume_from_checkpoint(ops: &[PlotOp], plotter: &mut impl Plottable, checkpoint_path: &Path) -> io::Result<()> {
+    let start = match fs::read_to_string(checkpoint_path) {
+        Ok(text) => text.trim().parse::<usize>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .min(ops.len()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e),
+    };
+
+    // Restore the color and position the plot had reached.
+    let done = &ops[.. start];
+    let color = done.iter().rev().find_map(|op| match op {
+        PlotOp::ChangeColor(color) => Some(color),
+        _ => None,
+    });
+    if let Some(color) = color {
+        plotter.change_color(color);
+    }
+    let velocity = done.iter().rev().find_map(|op| match op {
+        PlotOp::SetVelocity(cm_per_sec) => Some(*cm_per_sec),
+        _ => None,
+    });
+    if let Some(cm_per_sec) = velocity {
+        plotter.set_velocity(cm_per_sec);
+    }
+    let position = done.iter().rev().find_map(|op| match op {
+        PlotOp::MoveTo(x, y) | PlotOp::Draw(x, y) => Some((*x, *y)),
+        _ => None,
+    });
+    if let Some((x, y)) = position {
+        plotter.move_to(x, y);
+    }
+
+    replay_from(ops, start, plotter, checkpoint_path)
+}
+
+/// Send ops[start..] to a plotter, checkpointing the count of operations sent.
+fn replay_from(ops: &[PlotOp], start: usize, plotter: &mut impl Plottable, checkpoint_path: &Path) -> io::Result<()> {
+    for (i, op) in ops.iter().enumerate().skip(start) {
+        replay(std::slice::from_ref(op), plotter);
+        let sent = i + 1;
+        if sent % CHECKPOINT_EVERY == 0 {
+            plotter.flush(); // Only count operations that have left the program.
+            plotter.check_status()?; // A stopped plotter dropped them; keep the last good checkpoint.
+            fs::write(checkpoint_path, sent.to_string())?;
+        }
+    }
+    plotter.flush();
+    plotter.check_status()?;
+    match fs::remove_file(checkpoint_path) {
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Plot a design larger than the plotter can take on several sheets.  The design rectangle
+/// design_bounds is cut into tiles the size of sheet_bounds, starting from its lower left corner,
+/// and each tile is plotted on its own sheet with its lower left corner at the sheet's.
+/// Tiles are numbered from 0 along the bottom row, then each row above.
+///
+/// For each tile that has something on it, f(tile) is called for the plotter to draw it with,
+/// the operations are clipped to the tile, and an L-shaped registration mark is drawn in each corner
+/// of the sheet to line the sheets up by.  Before every sheet but the first, the user is asked to
+/// load a fresh sheet.  Each plotter is initialized and finalized here.
+///
+/// # Examples
+///
+/// ```
+/// plot_builder::plot_tiled(builder.ops(), (0.0, 0.0, 500.0, 300.0), (0.0, 0.0, 250.0, 200.0),
+///                          |_| Box::new(USCutter::new("COM12", 0.0, 0.0, 250.0, 200.0).unwrap()));
+/// ```
+///
+pub fn plot_tiled(ops: &[PlotOp], design_bounds: (f64, f64, f64, f64), sheet_bounds: (f64, f64, f64, f64),
+                  mut f: impl FnMut(usize) -> Box<dyn Plottable>) {
+    let (dllx, dlly, durx, dury) = design_bounds;
+    let (sllx, slly, surx, sury) = sheet_bounds;
+    let (sheet_w, sheet_h) = (surx - sllx, sury - slly);
+    if sheet_w <= 0.0 || sheet_h <= 0.0 {
+        panic!("Parameter `sheet_bounds` must have its upper right greater than its lower left.")
+    }
+    let cols = (((durx - dllx) / sheet_w).ceil() as usize).max(1);
+    let rows = (((dury - dlly) / sheet_h).ceil() as usize).max(1);
+
+    let mut sheets = 0;
+    for row in 0 .. rows {
+        for col in 0 .. cols {
+            let tile = row * cols + col;
+            let (tx, ty) = (dllx + col as f64 * sheet_w, dlly + row as f64 * sheet_h);
+            let tile_bounds = (tx, ty, tx + sheet_w, ty + sheet_h);
+            let (dx, dy) = (sllx - tx, slly - ty); // Design to sheet.
+            let tile_ops = clip_ops(ops, tile_bounds);
+            if !tile_ops.iter().any(|op| matches!(op, PlotOp::Draw(..))) {
+                continue;
+            }
+
+            if sheets > 0 {
+                println!("Load a fresh sheet for tile {} (row {}, column {}) and then hit enter", tile, row, col);
+                let mut input_line = String::new();
+                io::stdin().read_line(&mut input_line).expect("Error reading line.");
+            }
+            sheets += 1;
+
+            let mut plotter = f(tile);
+            plotter.initialize();
+            registration_marks(&mut plotter, sheet_bounds);
+            for op in &tile_ops {
+                match op {
+                    PlotOp::MoveTo(x, y) => plotter.move_to(x + dx, y + dy),
+                    PlotOp::Draw(x, y) => plotter.draw(x + dx, y + dy),
+                    PlotOp::PenUp => plotter.pen_up(),
+                    PlotOp::PenDown => plotter.pen_down(),
+                    PlotOp::ChangeColor(color) => plotter.change_color(color),
+                    PlotOp::SetVelocity(cm_per_sec) => plotter.set_velocity(*cm_per_sec),
+                }
+            }
+            plotter.finalize();
+        }
+    }
+}
+
+/// The operations clipped to bounds, in design coordinates.  Lines are cut with clip_line(), with a
+/// MoveTo wherever a line re-enters.  Color changes are kept only before lines that are drawn,
+/// and only when the color differs from the one in use, so a tile doesn't ask for needless pen changes.
+fn clip_ops(ops: &[PlotOp], bounds: (f64, f64, f64, f64)) -> Vec<PlotOp> {
+    let mut clipped = Vec::new();
+    let mut pos: Option<(f64, f64)> = None;  // Pen position in the design.
+    let mut at: Option<(f64, f64)> = None;   // Pen position in the clipped operations.
+    let mut color: Option<&String> = None;   // Color last asked for.
+    let mut in_use: Option<&String> = None;  // Color last emitted.
+    for op in ops {
+        match op {
+            PlotOp::MoveTo(x, y) => pos = Some((*x, *y)),
+            PlotOp::Draw(x, y) => {
+                let (x0, y0) = pos.unwrap_or((*x, *y));
+                pos = Some((*x, *y));
+                if let Some((ax, ay, bx, by)) = clip_line(x0, y0, *x, *y, bounds) {
+                    if color != in_use {
+                        if let Some(c) = color {
+                            clipped.push(PlotOp::ChangeColor(c.clone()));
+                        }
+                        in_use = color;
+                        at = None; // A color change lifts the pen.
+                    }
+                    if at != Some((ax, ay)) {
+                        clipped.push(PlotOp::MoveTo(ax, ay));
+                    }
+                    clipped.push(PlotOp::Draw(bx, by));
+                    at = Some((bx, by));
+                }
+            }
+            PlotOp::PenUp => {
+                if at.is_some() {
+                    clipped.push(PlotOp::PenUp);
+                    at = None;
+                }
+            }
+            PlotOp::PenDown => {
+                let (llx, lly, urx, ury) = bounds;
+                if let Some((x, y)) = pos.filter(|&(x, y)| x >= llx && x <= urx && y >= lly && y <= ury) {
+                    if color != in_use {
+                        if let Some(c) = color {
+                            clipped.push(PlotOp::ChangeColor(c.clone()));
+                        }
+                        in_use = color;
+                    }
+                    clipped.push(PlotOp::MoveTo(x, y));
+                    clipped.push(PlotOp::PenDown);
+                    at = Some((x, y));
+                }
+            }
+            PlotOp::ChangeColor(c) => color = Some(c),
+            PlotOp::SetVelocity(_) => clipped.push(op.clone()),
+        }
+    }
+    clipped
+}
+
+/// Draw an L in each corner of the sheet, along its edges.
+fn registration_marks(plotter: &mut impl Plottable, sheet_bounds: (f64, f64, f64, f64)) {
+    let (llx, lly, urx, ury) = sheet_bounds;
+    let arm = REGISTRATION_MARK_MM;
+    for &(x, y, sx, sy) in [(llx, lly, 1.0, 1.0), (urx, lly, -1.0, 1.0), (urx, ury, -1.0, -1.0), (llx, ury, 1.0, -1.0)].iter() {
+        plotter.move_to(x + sx * arm, y);
+        plotter.draw(x, y);
+        plotter.draw(x, y + sy * arm);
+    }
+    plotter.pen_up();
+}
+
+/// How far a design sticks out of the plot rectangle, found by validate_fit().
+/// Each overflow is in mm and zero if the design fits on that side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitError {
+    pub left_mm: f64,
+    pub right_mm: f64,
+    pub bottom_mm: f64,
+    pub top_mm: f64,
+    /// Extent of the design, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    pub bounding_box: (f64, f64, f64, f64),
+}
+
+impl fmt::Display for FitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (llx, lly, urx, ury) = self.bounding_box;
+        let overflows: Vec<String> = [("left", self.left_mm), ("right", self.right_mm),
+                                      ("bottom", self.bottom_mm), ("top", self.top_mm)].iter()
+            .filter(|&&(_, mm)| mm > 0.0)
+            .map(|&(side, mm)| format!("{:.1} mm over the {}", mm, side))
+            .collect();
+        write!(f, "design ({:.1}, {:.1}) to ({:.1}, {:.1}) mm does not fit: {}", llx, lly, urx, ury, overflows.join(", "))
+    }
+}
+
+impl Error for FitError {}
+
+/// Check that every point of a design lies within bounds = (llx_mm, lly_mm, urx_mm, ury_mm),
+/// before sending it to a plotter that would otherwise clip it.
+pub fn validate_fit(ops: &[PlotOp], bounds: (f64, f64, f64, f64)) -> Result<(), FitError> {
+    let (llx, lly, urx, ury) = bounds;
+    let (mut minx, mut miny) = (f64::INFINITY, f64::INFINITY);
+    let (mut maxx, mut maxy) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for op in ops {
+        if let PlotOp::MoveTo(x, y) | PlotOp::Draw(x, y) = op {
+            minx = minx.min(*x);
+            miny = miny.min(*y);
+            maxx = maxx.max(*x);
+            maxy = maxy.max(*y);
+        }
+    }
+    if minx > maxx {
+        return Ok(()); // Nothing to plot.
+    }
+    let error = FitError {
+        left_mm: (llx - minx).max(0.0),
+        right_mm: (maxx - urx).max(0.0),
+        bottom_mm: (lly - miny).max(0.0),
+        top_mm: (maxy - ury).max(0.0),
+        bounding_box: (minx, miny, maxx, maxy),
+    };
+    if error.left_mm > 0.0 || error.right_mm > 0.0 || error.bottom_mm > 0.0 || error.top_mm > 0.0 {
+        Err(error)
+    } else {
+        Ok(())
+    }
+}
+
+impl Plottable for PlotBuilder {
+
+    /// Nothing to prepare; provided for compatibility.
+    fn initialize(&mut self) {}
+
+    /// Nothing to finish; provided for compatibility.
+    fn finalize(&mut self) {}
+
+    /// Record a straight line from present position to absolute position (destx_mm, desty_mm).
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.ops.push(PlotOp::Draw(destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Record a move without drawing to absolute position (destx_mm, desty_mm).
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.ops.push(PlotOp::MoveTo(destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Record a line from present position (dx, dy) mm, as an absolute Draw.
+    /// Returns the new position of the pen.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Record a move from present position (dx, dy) mm, as an absolute MoveTo.
+    /// Returns the new position of the pen.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Record raising the pen.
+    fn pen_up(&mut self) {
+        self.ops.push(PlotOp::PenUp);
+    }
+
+    /// Record lowering the pen in place.
+    fn pen_down(&mut self) {
+        self.ops.push(PlotOp::PenDown);
+    }
+
+    /// Record a pen color change.
+    fn change_color(&mut self, color_name: &str) {
+        self.ops.push(PlotOp::ChangeColor(color_name.to_string()));
+    }
+
+    /// Record a pen speed change.
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.ops.push(PlotOp::SetVelocity(cm_per_sec));
+    }
+
+    /// The plot rectangle given when the builder was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+    use crate::uscutter::USCutter;
+
+    #[test]
+    fn stopped_plot_keeps_its_checkpoint() {
+        let dir = std::env::temp_dir();
+        let checkpoint = dir.join("rplotter_test_stopped.checkpoint");
+        let plot_file = dir.join("rplotter_test_stopped.plt");
+        let ops: Vec<PlotOp> = (0 .. 3 * CHECKPOINT_EVERY).map(|i| PlotOp::Draw(i as f64, 10.0)).collect();
+        fs::write(&checkpoint, CHECKPOINT_EVERY.to_string()).unwrap(); // From an earlier, interrupted run.
+
+        let mut cutter = USCutter::to_file(&plot_file, 0.0, 0.0, 100.0, 100.0).unwrap();
+        cutter.set_time_budget(Duration::from_millis(0));
+        cutter.initialize();
+        thread::sleep(Duration::from_millis(2));
+        let result = resume_from_checkpoint(&ops, &mut cutter, &checkpoint);
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(fs::read_to_string(&checkpoint).unwrap(), CHECKPOINT_EVERY.to_string());
+        fs::remove_file(&checkpoint).unwrap();
+        fs::remove_file(&plot_file).unwrap();
+    }
+}
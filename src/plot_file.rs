@@ -0,0 +1,377 @@
+//! plot_file module runs a whole plot from a definition file, so a plot can be set up and
+//! changed without recompiling.  The file is a small subset of TOML: `key = value` settings,
+//! then one `[[shape]]` table per thing to draw.  Values are numbers, "strings", true/false,
+//! or [lists, of, numbers].
+//!
+//! ```text
+//! # Three layered hypotrochoids, previewed on screen.
+//! backend = "turtle"          # "cutter", "file" or "turtle"
+//! port = "COM12"              # for backend = "cutter"
+//! output = "plot.plt"         # for backend = "file"
+//! bounds = [-40, -40, 40, 40] # llx, lly, urx, ury in mm
+//! simplify = 0.05             # optional: SimplifyPlotter tolerance in mm
+//! fit_to_page = false         # optional: scale the design to fill the bounds
+//!
+//! [[shape]]
+//! kind = "hypotrochoid"
+//! color = "cyan"
+//! rolling_radius = 17.1
+//! pen_radius = 11.4
+//! inner = 7
+//! outer = 12
+//! ```
+//!
+//! Shapes and their parameters (all lengths in mm, angles in radians; those with a value
+//! shown are optional, and every shape takes an optional `color`):
+//!
+//! ```text
+//! hypotrochoid  rolling_radius pen_radius inner outer x=0 y=0 rot=0
+//! guilloche     radius amplitude frequency count phase_step x=0 y=0 rot=0
+//! circle        radius x=0 y=0
+//! ellipse       rx ry x=0 y=0 rot=0
+//! sine_wave     amplitude wavelength x_start x_end y=0 phase=0 samples=100
+//! text          text height x=0 y=0 rot=0 align="left"
+//! wallpaper     preset ("rosettes", "stars" or "bricks"), covering the bounds
+//! ```
+//!
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::curves;
+use crate::pipeline::PlotterBuilder;
+use crate::plottable::Plottable;
+use crate::roulette::{self, WallpaperPreset};
+use crate::script::ScriptError;
+use crate::shapes;
+use crate::text::{self, Align};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    List(Vec<f64>),
+}
+
+impl Value {
+    fn describe(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "a number",
+            Value::Text(_) => "a string",
+            Value::Bool(_) => "true or false",
+            Value::List(_) => "a list",
+        }
+    }
+}
+
+/// The settings of one table of the file, each with the line it is on.
+/// Settings are taken out as they are used, so any left over are unknown.
+struct Table {
+    line: usize, // Line of the table header, or 0 for the top level.
+    entries: HashMap<String, (Value, usize)>,
+}
+
+impl Table {
+    fn new(line: usize) -> Table {
+        Table { line, entries: HashMap::new() }
+    }
+
+    fn take(&mut self, key: &str) -> Option<(Value, usize)> {
+        self.entries.remove(key)
+    }
+
+    fn missing(&self, key: &str) -> ScriptError {
+        let place = if self.line == 0 { "the file" } else { "this shape" };
+        ScriptError { line: self.line.max(1), message: format!("{} needs `{}`", place, key) }
+    }
+
+    fn number(&mut self, key: &str) -> Result<f64, ScriptError> {
+        match self.take(key) {
+            Some((Value::Number(x), _)) => Ok(x),
+            Some((other, line)) => Err(wrong_type(key, "a number", &other, line)),
+            None => Err(self.missing(key)),
+        }
+    }
+
+    fn number_or(&mut self, key: &str, default: f64) -> Result<f64, ScriptError> {
+        if self.entries.contains_key(key) { self.number(key) } else { Ok(default) }
+    }
+
+    fn integer(&mut self, key: &str) -> Result<i32, ScriptError> {
+        let line = self.entries.get(key).map_or(self.line, |e| e.1);
+        let x = self.number(key)?;
+        if x.fract() != 0.0 {
+            return Err(ScriptError { line, message: format!("`{}` must be a whole number", key) });
+        }
+        Ok(x as i32)
+    }
+
+    fn text(&mut self, key: &str) -> Result<String, ScriptError> {
+        match self.take(key) {
+            Some((Value::Text(s), _)) => Ok(s),
+            Some((other, line)) => Err(wrong_type(key, "a string", &other, line)),
+            None => Err(self.missing(key)),
+        }
+    }
+
+    fn text_or(&mut self, key: &str, default: &str) -> Result<String, ScriptError> {
+        if self.entries.contains_key(key) { self.text(key) } else { Ok(default.to_string()) }
+    }
+
+    fn optional_text(&mut self, key: &str) -> Result<Option<String>, ScriptError> {
+        if self.entries.contains_key(key) { self.text(key).map(Some) } else { Ok(None) }
+    }
+
+    fn bool_or(&mut self, key: &str, default: bool) -> Result<bool, ScriptError> {
+        match self.take(key) {
+            Some((Value::Bool(b), _)) => Ok(b),
+            Some((other, line)) => Err(wrong_type(key, "true or false", &other, line)),
+            None => Ok(default),
+        }
+    }
+
+    fn list(&mut self, key: &str, len: usize) -> Result<Vec<f64>, ScriptError> {
+        match self.take(key) {
+            Some((Value::List(v), line)) => {
+                if v.len() != len {
+                    return Err(ScriptError { line, message: format!("`{}` must have {} numbers, found {}", key, len, v.len()) });
+                }
+                Ok(v)
+            }
+            Some((other, line)) => Err(wrong_type(key, "a list", &other, line)),
+            None => Err(self.missing(key)),
+        }
+    }
+
+    /// Error for the first setting that was never used, if any.
+    fn check_all_used(&self) -> Result<(), ScriptError> {
+        match self.entries.iter().min_by_key(|(_, &(_, line))| line) {
+            Some((key, &(_, line))) => Err(ScriptError { line, message: format!("unknown parameter `{}`", key) }),
+            None => Ok(()),
+        }
+    }
+}
+
+fn wrong_type(key: &str, expected: &str, found: &Value, line: usize) -> ScriptError {
+    ScriptError { line, message: format!("`{}` must be {}, found {}", key, expected, found.describe()) }
+}
+
+enum Backend {
+    Cutter(String),
+    File(String),
+    Turtle,
+}
+
+enum ShapeKind {
+    Hypotrochoid { rolling_radius: f64, pen_radius: f64, inner: i32, outer: i32, x: f64, y: f64, rot: f64 },
+    Guilloche { radius: f64, amplitude: f64, frequency: i32, count: i32, phase_step: f64, x: f64, y: f64, rot: f64 },
+    Circle { radius: f64, x: f64, y: f64 },
+    Ellipse { rx: f64, ry: f64, x: f64, y: f64, rot: f64 },
+    SineWave { amplitude: f64, wavelength: f64, x_start: f64, x_end: f64, y: f64, phase: f64, samples: u32 },
+    Text { text: String, height: f64, x: f64, y: f64, rot: f64, align: Align },
+    Wallpaper(WallpaperPreset),
+}
+
+struct Shape {
+    color: Option<String>,
+    kind: ShapeKind,
+}
+
+/// Run the plot described by the file at path: open the backend, draw each shape in order,
+/// and finish the plot.  The whole file is checked before the backend is opened, so a mistake
+/// stops it with a ScriptError giving the line, before anything is plotted.
+///
+/// # Examples
+///
+/// ```
+/// plot_file::run_plot_file(Path::new("rosettes.toml"))?;
+/// ```
+///
+pub fn run_plot_file(path: &Path) -> Result<(), Box<dyn Error>> {
+    let source = fs::read_to_string(path)?;
+    let (mut settings, tables) = parse_tables(&source)?;
+
+    let backend_line = settings.entries.get("backend").map_or(1, |e| e.1);
+    let backend = match settings.text("backend")?.as_str() {
+        "cutter" => Backend::Cutter(settings.text("port")?),
+        "file" => Backend::File(settings.text("output")?),
+        "turtle" => Backend::Turtle,
+        other => return Err(Box::new(ScriptError {
+            line: backend_line,
+            message: format!("unknown backend `{}`; use \"cutter\", \"file\" or \"turtle\"", other),
+        })),
+    };
+    let b = settings.list("bounds", 4)?;
+    let bounds = (b[0], b[1], b[2], b[3]);
+    let simplify = settings.number_or("simplify", 0.0)?;
+    let fit_to_page = settings.bool_or("fit_to_page", false)?;
+    settings.check_all_used()?;
+
+    let shapes = tables.into_iter().map(parse_shape).collect::<Result<Vec<Shape>, ScriptError>>()?;
+
+    let mut builder = PlotterBuilder::new();
+    if fit_to_page {
+        builder = builder.with_fit_to_page();
+    }
+    if simplify > 0.0 {
+        builder = builder.with_simplify(simplify);
+    }
+    let mut plotter = match backend {
+        Backend::Cutter(port) => builder.to_cutter(&port, bounds)?,
+        Backend::File(output) => builder.to_file(Path::new(&output), bounds)?,
+        Backend::Turtle => builder.to_turtle(bounds),
+    };
+    plotter.initialize();
+    for shape in &shapes {
+        draw_shape(&mut plotter, shape, bounds);
+    }
+    plotter.finalize();
+    Ok(())
+}
+
+fn draw_shape(plotter: &mut impl Plottable, shape: &Shape, bounds: (f64, f64, f64, f64)) {
+    if let Some(color) = &shape.color {
+        plotter.change_color(color);
+    }
+    match shape.kind {
+        ShapeKind::Hypotrochoid { rolling_radius, pen_radius, inner, outer, x, y, rot } =>
+            roulette::full_hypotrochoid(plotter, rolling_radius, pen_radius, inner, outer, x, y, rot),
+        ShapeKind::Guilloche { radius, amplitude, frequency, count, phase_step, x, y, rot } =>
+            roulette::guilloche(plotter, radius, amplitude, frequency, phase_step, count, x, y, rot),
+        ShapeKind::Circle { radius, x, y } => shapes::circle_at(plotter, x, y, radius),
+        ShapeKind::Ellipse { rx, ry, x, y, rot } => plotter.ellipse(x, y, rx, ry, rot),
+        ShapeKind::SineWave { amplitude, wavelength, x_start, x_end, y, phase, samples } =>
+            curves::sine_wave(plotter, amplitude, wavelength, phase, x_start, x_end, y, samples),
+        ShapeKind::Text { ref text, height, x, y, rot, align } =>
+            text::draw_text(plotter, text, x, y, height, rot, align),
+        ShapeKind::Wallpaper(preset) => roulette::roulette_wallpaper(plotter, bounds, preset),
+    }
+}
+
+/// Check one [[shape]] table, including the values the drawing functions would panic on.
+fn parse_shape(mut t: Table) -> Result<Shape, ScriptError> {
+    let line = t.line;
+    let invalid = |message: &str| ScriptError { line, message: message.to_string() };
+    let color = t.optional_text("color")?;
+    let kind = match t.text("kind")?.as_str() {
+        "hypotrochoid" => {
+            let (inner, outer) = (t.integer("inner")?, t.integer("outer")?);
+            if inner < 1 || inner > outer {
+                return Err(invalid("`inner` must be from 1 to `outer`"));
+            }
+            ShapeKind::Hypotrochoid {
+                rolling_radius: t.number("rolling_radius")?, pen_radius: t.number("pen_radius")?, inner, outer,
+                x: t.number_or("x", 0.0)?, y: t.number_or("y", 0.0)?, rot: t.number_or("rot", 0.0)?,
+            }
+        }
+        "guilloche" => {
+            let (radius, amplitude, frequency) = (t.number("radius")?, t.number("amplitude")?, t.integer("frequency")?);
+            if frequency < 1 || amplitude >= radius {
+                return Err(invalid("`frequency` must be at least 1 and `amplitude` less than `radius`"));
+            }
+            ShapeKind::Guilloche {
+                radius, amplitude, frequency, count: t.integer("count")?, phase_step: t.number("phase_step")?,
+                x: t.number_or("x", 0.0)?, y: t.number_or("y", 0.0)?, rot: t.number_or("rot", 0.0)?,
+            }
+        }
+        "circle" => ShapeKind::Circle { radius: t.number("radius")?, x: t.number_or("x", 0.0)?, y: t.number_or("y", 0.0)? },
+        "ellipse" => ShapeKind::Ellipse {
+            rx: t.number("rx")?, ry: t.number("ry")?,
+            x: t.number_or("x", 0.0)?, y: t.number_or("y", 0.0)?, rot: t.number_or("rot", 0.0)?,
+        },
+        "sine_wave" => {
+            let (wavelength, samples) = (t.number("wavelength")?, t.number_or("samples", 100.0)?);
+            if wavelength <= 0.0 || samples < 2.0 {
+                return Err(invalid("`wavelength` must be greater than zero and `samples` at least 2"));
+            }
+            ShapeKind::SineWave {
+                amplitude: t.number("amplitude")?, wavelength, x_start: t.number("x_start")?, x_end: t.number("x_end")?,
+                y: t.number_or("y", 0.0)?, phase: t.number_or("phase", 0.0)?, samples: samples as u32,
+            }
+        }
+        "text" => {
+            let align = match t.text_or("align", "left")?.as_str() {
+                "left" => Align::Left,
+                "center" => Align::Center,
+                "right" => Align::Right,
+                other => return Err(invalid(&format!("unknown align `{}`; use \"left\", \"center\" or \"right\"", other))),
+            };
+            ShapeKind::Text {
+                text: t.text("text")?, height: t.number("height")?,
+                x: t.number_or("x", 0.0)?, y: t.number_or("y", 0.0)?, rot: t.number_or("rot", 0.0)?, align,
+            }
+        }
+        "wallpaper" => ShapeKind::Wallpaper(match t.text("preset")?.as_str() {
+            "rosettes" => WallpaperPreset::Rosettes,
+            "stars" => WallpaperPreset::Stars,
+            "bricks" => WallpaperPreset::Bricks,
+            other => return Err(invalid(&format!("unknown preset `{}`", other))),
+        }),
+        other => return Err(invalid(&format!("unknown shape kind `{}`", other))),
+    };
+    t.check_all_used()?;
+    Ok(Shape { color, kind })
+}
+
+/// Split the file into the top-level settings and the [[shape]] tables.
+fn parse_tables(source: &str) -> Result<(Table, Vec<Table>), ScriptError> {
+    let mut settings = Table::new(0);
+    let mut shapes: Vec<Table> = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let error = |message: String| ScriptError { line, message };
+        let text = strip_comment(text).trim();
+        if text.is_empty() {
+            continue;
+        }
+        if text.starts_with('[') {
+            if text != "[[shape]]" {
+                return Err(error(format!("unknown table `{}`; only [[shape]] is allowed", text)));
+            }
+            shapes.push(Table::new(line));
+            continue;
+        }
+        let eq = text.find('=').ok_or_else(|| error(format!("expected `key = value`, found `{}`", text)))?;
+        let key = text[.. eq].trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(error(format!("`{}` is not a valid key", key)));
+        }
+        let value = parse_value(text[eq + 1 ..].trim()).map_err(error)?;
+        let table = shapes.last_mut().unwrap_or(&mut settings);
+        if table.entries.insert(key.to_string(), (value, line)).is_some() {
+            return Err(error(format!("`{}` is set twice", key)));
+        }
+    }
+    Ok((settings, shapes))
+}
+
+/// The line without any # comment, leaving # inside strings alone.
+fn strip_comment(text: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &text[.. i],
+            _ => {}
+        }
+    }
+    text
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        Ok(Value::Text(text[1 .. text.len() - 1].to_string()))
+    } else if text == "true" || text == "false" {
+        Ok(Value::Bool(text == "true"))
+    } else if text.starts_with('[') && text.ends_with(']') {
+        text[1 .. text.len() - 1].split(',')
+            .map(|item| item.trim().parse::<f64>().map_err(|_| format!("`{}` is not a number", item.trim())))
+            .collect::<Result<Vec<f64>, String>>()
+            .map(Value::List)
+    } else {
+        text.parse::<f64>().map(Value::Number).map_err(|_| format!("`{}` is not a number, string, true/false or list", text))
+    }
+}
@@ -1,3 +1,14 @@
+use std::f64::consts::PI;
+use std::io;
+
+use crate::fill;
+use crate::geometry::points_along;
+
+const ARC_STEP_RAD: f64 = PI / 36.0; // Largest angle covered by one segment of an approximated arc.
+const WEIGHT_STEP_MM: f64 = 1.0;     // Longest piece of a weighted stroke drawn with one weight.
+const PASS_OFFSET_MM: f64 = 0.3;     // Distance between side-by-side passes of a weighted stroke.
+const SECTOR_HATCH_MM: f64 = 0.5;    // Spacing of the lines filling a sector.
+
 pub trait Plottable {
     fn initialize(&mut self);
     fn finalize(&mut self);
@@ -6,5 +17,351 @@ pub trait Plottable {
     fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64);
     fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64);
     fn pen_up(&mut self);
+    /// Lower the pen where it is, without moving.
+    fn pen_down(&mut self);
     fn change_color(&mut self, color_name: &str);
+    /// Plot rectangle as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64);
+    /// Present position of the pen as (x_mm, y_mm), where the last draw or move left it.
+    fn position(&self) -> (f64, f64);
+
+    /// Returns an error if the plot was stopped before it was finished, e.g. by a USCutter
+    /// running out of time or losing its connection.  Other plotters can't fail, and return Ok.
+    fn check_status(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Set the pen speed in cm/s, e.g. slower for cleaner lines on slick media.  Plotters without
+    /// speed control ignore it.  See USCutter::set_velocity() for the range a plotter accepts.
+    fn set_velocity(&mut self, _cm_per_sec: f64) {}
+
+    /// Move with the pen up to the lower left corner of the plot rectangle, e.g. to reload media
+    /// part way through a plot.  Unlike finalize(), the plotter stays ready to draw.
+    fn home(&mut self) {
+        let (llx_mm, lly_mm, _, _) = self.bounds();
+        self.move_to(llx_mm, lly_mm);
+    }
+
+    /// Send on anything held back so far, e.g. commands a USCutter has buffered or paths a
+    /// wrapper is still collecting, so that everything drawn up to now reaches the device.
+    /// Plotters that don't hold anything back ignore it.
+    fn flush(&mut self) {}
+
+    /// Draw to a position given as fractions (0..1) of the plot rectangle,
+    /// e.g. (0.5, 0.5) is the center regardless of the bounds.
+    fn draw_frac(&mut self, fx: f64, fy: f64) {
+        let (x, y) = frac2mm(self.bounds(), fx, fy);
+        self.draw(x, y);
+    }
+
+    /// Move without drawing to a position given as fractions (0..1) of the plot rectangle.
+    fn move_frac(&mut self, fx: f64, fy: f64) {
+        let (x, y) = frac2mm(self.bounds(), fx, fy);
+        self.move_to(x, y);
+    }
+
+    /// Draw the polyline through points: move to the first, then draw to each of the rest.
+    /// Does nothing if points is empty.
+    fn draw_path(&mut self, points: &[(f64, f64)]) {
+        if let Some(&(x, y)) = points.first() {
+            self.move_to(x, y);
+            for &(x, y) in &points[1 ..] {
+                self.draw(x, y);
+            }
+        }
+    }
+
+    /// Make a dot at (x_mm, y_mm): move there, then lower the pen in place.
+    fn dot(&mut self, x_mm: f64, y_mm: f64) {
+        self.move_to(x_mm, y_mm);
+        self.pen_down();
+    }
+
+    /// Draw part of an axis-aligned ellipse centered at (cx_mm, cy_mm) with radii rx_mm, ry_mm,
+    /// starting at angle start_rad and sweeping sweep_rad (positive is CCW).
+    /// The pen moves to the start of the arc first, and is left at its end.
+    fn ellipse_arc(&mut self, cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, start_rad: f64, sweep_rad: f64) {
+        let steps = ((sweep_rad.abs() / ARC_STEP_RAD).ceil() as i32).max(1);
+        self.move_to(cx_mm + rx_mm * start_rad.cos(), cy_mm + ry_mm * start_rad.sin());
+        for i in 1 .. (steps + 1) {
+            let t = start_rad + sweep_rad * i as f64 / steps as f64;
+            self.draw(cx_mm + rx_mm * t.cos(), cy_mm + ry_mm * t.sin());
+        }
+    }
+
+    /// Draw a circle of radius_mm centered on the present position, like HPGL's CI: the pen moves
+    /// out to the circle at angle 0 (+x), draws it CCW, and comes back to the center with the pen up.
+    fn circle(&mut self, radius_mm: f64) {
+        self.arc(radius_mm, 360.0);
+    }
+
+    /// Draw part of the circle of radius_mm centered on the present position, starting at angle 0
+    /// (+x) and sweeping sweep_deg (positive is CCW).  As with circle(), the pen comes back to the center.
+    fn arc(&mut self, radius_mm: f64, sweep_deg: f64) {
+        let (cx, cy) = self.position();
+        self.ellipse_arc(cx, cy, radius_mm, radius_mm, 0.0, sweep_deg.to_radians());
+        self.move_to(cx, cy);
+    }
+
+    /// Draw a whole ellipse centered at (cx_mm, cy_mm) with radii rx_mm, ry_mm, its rx axis at
+    /// angle rot_rad (positive is CCW) from the x axis.  The pen starts and ends at the end of the rx axis.
+    fn ellipse(&mut self, cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, rot_rad: f64) {
+        let steps = (2.0 * PI / ARC_STEP_RAD).ceil() as i32;
+        let (x, y) = ellipse_point(cx_mm, cy_mm, rx_mm, ry_mm, rot_rad, 0.0);
+        self.move_to(x, y);
+        for i in 1 .. (steps + 1) {
+            let (x, y) = ellipse_point(cx_mm, cy_mm, rx_mm, ry_mm, rot_rad, 2.0 * PI * i as f64 / steps as f64);
+            self.draw(x, y);
+        }
+    }
+
+    /// Draw the polyline through points with a thickness that follows weights, one per point,
+    /// interpolated along each segment.  A weight of n is drawn as n side-by-side passes
+    /// PASS_OFFSET_MM apart, so 1 is an ordinary line, and a weight that rounds to 0 leaves a gap.
+    fn draw_weighted(&mut self, points: &[(f64, f64)], weights: &[f64]) {
+        let samples = weighted_samples(points, weights);
+        let max_passes = samples.iter().map(|s| passes(s.4)).max().unwrap_or(0);
+        for pass in 0 .. max_passes {
+            // Passes alternate sides of the center line so the stroke widens evenly,
+            // and alternate direction to save travel.
+            let side = if pass % 2 == 1 { 1.0 } else { -1.0 };
+            let offset = side * ((pass + 1) / 2) as f64 * PASS_OFFSET_MM;
+            let mut down = false;
+            let mut draw_pass = |&(x, y, nx, ny, w): &(f64, f64, f64, f64, f64)| {
+                let (px, py) = (x + nx * offset, y + ny * offset);
+                if passes(w) <= pass {
+                    down = false;
+                } else if down {
+                    self.draw(px, py);
+                } else {
+                    self.move_to(px, py);
+                    down = true;
+                }
+            };
+            if pass % 2 == 0 {
+                samples.iter().for_each(&mut draw_pass);
+            } else {
+                samples.iter().rev().for_each(&mut draw_pass);
+            }
+        }
+        self.pen_up();
+    }
+
+    /// Make a row of dots (holes, with a pouncing tool) along the polyline through points, evenly
+    /// spaced by arc length about hole_spacing_mm apart and including both ends, for fold and tear lines.
+    /// Each hole is made with dot().
+    fn perforate(&mut self, points: &[(f64, f64)], hole_spacing_mm: f64) {
+        if hole_spacing_mm <= 0.0 {
+            panic!("Parameter `hole_spacing_mm` must be greater than zero.")
+        }
+        for (x, y) in points_along(points, hole_spacing_mm) {
+            self.dot(x, y);
+        }
+        self.pen_up();
+    }
+
+    /// Draw a pie slice of the circle centered at (cx_mm, cy_mm) with radius radius_mm: the radius
+    /// at start_rad, the arc from start_rad to end_rad (CCW if end_rad is larger), and the radius
+    /// back to the center.  If filled is true, the slice is also hatched with lines SECTOR_HATCH_MM apart.
+    fn sector(&mut self, cx_mm: f64, cy_mm: f64, radius_mm: f64, start_rad: f64, end_rad: f64, filled: bool) {
+        let outline = sector_outline(cx_mm, cy_mm, radius_mm, start_rad, end_rad);
+        self.move_to(cx_mm, cy_mm);
+        for &(x, y) in &outline[1 ..] {
+            self.draw(x, y);
+        }
+        self.draw(cx_mm, cy_mm);
+        if filled {
+            fill_sector(self, cx_mm, cy_mm, radius_mm, start_rad, end_rad);
+        }
+    }
+}
+
+/// Vertices of a sector: its center, then points along its arc from start_rad to end_rad.
+pub fn sector_outline(cx_mm: f64, cy_mm: f64, radius_mm: f64, start_rad: f64, end_rad: f64) -> Vec<(f64, f64)> {
+    let sweep = end_rad - start_rad;
+    let steps = ((sweep.abs() / ARC_STEP_RAD).ceil() as i32).max(1);
+    let mut outline = vec![(cx_mm, cy_mm)];
+    for i in 0 .. (steps + 1) {
+        let t = start_rad + sweep * i as f64 / steps as f64;
+        outline.push((cx_mm + radius_mm * t.cos(), cy_mm + radius_mm * t.sin()));
+    }
+    outline
+}
+
+/// Hatch the inside of a sector, with lines along its middle radius so that neighboring
+/// slices of a pie are hatched at different angles.
+pub fn fill_sector<P: Plottable + ?Sized>(plotter: &mut P, cx_mm: f64, cy_mm: f64, radius_mm: f64, start_rad: f64, end_rad: f64) {
+    let outline = sector_outline(cx_mm, cy_mm, radius_mm, start_rad, end_rad);
+    let mut plotter = plotter;
+    fill::hatch_fill(&mut plotter, &outline, SECTOR_HATCH_MM, (start_rad + end_rad) / 2.0);
+}
+
+/// The point at parameter t_rad on an ellipse centered at (cx_mm, cy_mm) with radii rx_mm, ry_mm,
+/// rotated by rot_rad.
+pub fn ellipse_point(cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, rot_rad: f64, t_rad: f64) -> (f64, f64) {
+    let (ex, ey) = (rx_mm * t_rad.cos(), ry_mm * t_rad.sin());
+    let (sin, cos) = rot_rad.sin_cos();
+    (cx_mm + ex * cos - ey * sin, cy_mm + ex * sin + ey * cos)
+}
+
+/// Number of passes drawn for a weight.
+fn passes(weight: f64) -> usize {
+    weight.round().max(0.0) as usize
+}
+
+/// Points along a weighted polyline no more than WEIGHT_STEP_MM apart, as (x, y, nx, ny, weight)
+/// where (nx, ny) is the unit normal of the segment the point is on.  Points at a corner appear
+/// once for each segment, with that segment's normal.
+pub fn weighted_samples(points: &[(f64, f64)], weights: &[f64]) -> Vec<(f64, f64, f64, f64, f64)> {
+    if weights.len() != points.len() {
+        panic!("Parameter `weights` must have one weight for each point.");
+    }
+    let mut samples = Vec::new();
+    for i in 1 .. points.len() {
+        let ((x0, y0), (x1, y1)) = (points[i - 1], points[i]);
+        let (w0, w1) = (weights[i - 1], weights[i]);
+        let length = (x1 - x0).hypot(y1 - y0);
+        if length == 0.0 {
+            continue;
+        }
+        let (nx, ny) = (-(y1 - y0) / length, (x1 - x0) / length);
+        let steps = ((length / WEIGHT_STEP_MM).ceil() as usize).max(1);
+        for k in 0 .. (steps + 1) {
+            let t = k as f64 / steps as f64;
+            samples.push((x0 + t * (x1 - x0), y0 + t * (y1 - y0), nx, ny, w0 + t * (w1 - w0)));
+        }
+    }
+    samples
+}
+
+/// Convert fractions of the plot rectangle to absolute mm.
+fn frac2mm(bounds: (f64, f64, f64, f64), fx: f64, fy: f64) -> (f64, f64) {
+    let (llx_mm, lly_mm, urx_mm, ury_mm) = bounds;
+    (llx_mm + fx * (urx_mm - llx_mm), lly_mm + fy * (ury_mm - lly_mm))
+}
+
+/// A boxed plotter is a plotter, so wrappers and helpers generic over Plottable also accept
+/// Box<dyn Plottable>.  Every method is forwarded, so the boxed plotter's own versions of the
+/// provided methods are used.
+impl<T: Plottable + ?Sized> Plottable for Box<T> {
+    fn initialize(&mut self) { (**self).initialize() }
+    fn finalize(&mut self) { (**self).finalize() }
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) { (**self).draw(destx_mm, desty_mm) }
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) { (**self).move_to(destx_mm, desty_mm) }
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) { (**self).draw_relative(dx_mm, dy_mm) }
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) { (**self).move_relative(dx_mm, dy_mm) }
+    fn pen_up(&mut self) { (**self).pen_up() }
+    fn pen_down(&mut self) { (**self).pen_down() }
+    fn change_color(&mut self, color_name: &str) { (**self).change_color(color_name) }
+    fn bounds(&self) -> (f64, f64, f64, f64) { (**self).bounds() }
+    fn position(&self) -> (f64, f64) { (**self).position() }
+    fn check_status(&self) -> io::Result<()> { (**self).check_status() }
+    fn set_velocity(&mut self, cm_per_sec: f64) { (**self).set_velocity(cm_per_sec) }
+    fn home(&mut self) { (**self).home() }
+    fn flush(&mut self) { (**self).flush() }
+    fn draw_frac(&mut self, fx: f64, fy: f64) { (**self).draw_frac(fx, fy) }
+    fn move_frac(&mut self, fx: f64, fy: f64) { (**self).move_frac(fx, fy) }
+    fn draw_path(&mut self, points: &[(f64, f64)]) { (**self).draw_path(points) }
+    fn dot(&mut self, x_mm: f64, y_mm: f64) { (**self).dot(x_mm, y_mm) }
+    fn circle(&mut self, radius_mm: f64) { (**self).circle(radius_mm) }
+    fn arc(&mut self, radius_mm: f64, sweep_deg: f64) { (**self).arc(radius_mm, sweep_deg) }
+    fn ellipse_arc(&mut self, cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, start_rad: f64, sweep_rad: f64) {
+        (**self).ellipse_arc(cx_mm, cy_mm, rx_mm, ry_mm, start_rad, sweep_rad)
+    }
+    fn ellipse(&mut self, cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, rot_rad: f64) {
+        (**self).ellipse(cx_mm, cy_mm, rx_mm, ry_mm, rot_rad)
+    }
+    fn draw_weighted(&mut self, points: &[(f64, f64)], weights: &[f64]) { (**self).draw_weighted(points, weights) }
+    fn perforate(&mut self, points: &[(f64, f64)], hole_spacing_mm: f64) { (**self).perforate(points, hole_spacing_mm) }
+    fn sector(&mut self, cx_mm: f64, cy_mm: f64, radius_mm: f64, start_rad: f64, end_rad: f64, filled: bool) {
+        (**self).sector(cx_mm, cy_mm, radius_mm, start_rad, end_rad, filled)
+    }
+}
+
+/// Likewise a borrowed plotter, so a wrapper can be put in front of a plotter that is used again afterwards.
+impl<T: Plottable + ?Sized> Plottable for &mut T {
+    fn initialize(&mut self) { (**self).initialize() }
+    fn finalize(&mut self) { (**self).finalize() }
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) { (**self).draw(destx_mm, desty_mm) }
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) { (**self).move_to(destx_mm, desty_mm) }
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) { (**self).draw_relative(dx_mm, dy_mm) }
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) { (**self).move_relative(dx_mm, dy_mm) }
+    fn pen_up(&mut self) { (**self).pen_up() }
+    fn pen_down(&mut self) { (**self).pen_down() }
+    fn change_color(&mut self, color_name: &str) { (**self).change_color(color_name) }
+    fn bounds(&self) -> (f64, f64, f64, f64) { (**self).bounds() }
+    fn position(&self) -> (f64, f64) { (**self).position() }
+    fn check_status(&self) -> io::Result<()> { (**self).check_status() }
+    fn set_velocity(&mut self, cm_per_sec: f64) { (**self).set_velocity(cm_per_sec) }
+    fn home(&mut self) { (**self).home() }
+    fn flush(&mut self) { (**self).flush() }
+    fn draw_frac(&mut self, fx: f64, fy: f64) { (**self).draw_frac(fx, fy) }
+    fn move_frac(&mut self, fx: f64, fy: f64) { (**self).move_frac(fx, fy) }
+    fn draw_path(&mut self, points: &[(f64, f64)]) { (**self).draw_path(points) }
+    fn dot(&mut self, x_mm: f64, y_mm: f64) { (**self).dot(x_mm, y_mm) }
+    fn circle(&mut self, radius_mm: f64) { (**self).circle(radius_mm) }
+    fn arc(&mut self, radius_mm: f64, sweep_deg: f64) { (**self).arc(radius_mm, sweep_deg) }
+    fn ellipse_arc(&mut self, cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, start_rad: f64, sweep_rad: f64) {
+        (**self).ellipse_arc(cx_mm, cy_mm, rx_mm, ry_mm, start_rad, sweep_rad)
+    }
+    fn ellipse(&mut self, cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, rot_rad: f64) {
+        (**self).ellipse(cx_mm, cy_mm, rx_mm, ry_mm, rot_rad)
+    }
+    fn draw_weighted(&mut self, points: &[(f64, f64)], weights: &[f64]) { (**self).draw_weighted(points, weights) }
+    fn perforate(&mut self, points: &[(f64, f64)], hole_spacing_mm: f64) { (**self).perforate(points, hole_spacing_mm) }
+    fn sector(&mut self, cx_mm: f64, cy_mm: f64, radius_mm: f64, start_rad: f64, end_rad: f64, filled: bool) {
+        (**self).sector(cx_mm, cy_mm, radius_mm, start_rad, end_rad, filled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::gcode_plot::{GcodePlotter, GcodeSettings};
+    use crate::plot_builder::PlotBuilder;
+    use crate::svg_plot::SvgPlotter;
+    use crate::testing::MockPlotter;
+    use crate::uscutter::USCutter;
+
+    #[test]
+    fn different_plotters_work_as_trait_objects() {
+        let hpgl_path = std::env::temp_dir().join("rplotter_test_trait_objects.plt");
+        let mut plotters: Vec<Box<dyn Plottable>> = vec![
+            Box::new(USCutter::to_file(&hpgl_path, 0.0, 0.0, 50.0, 50.0).unwrap()),
+            Box::new(MockPlotter::new(0.0, 0.0, 50.0, 50.0)),
+        ];
+        for plotter in plotters.iter_mut() {
+            plotter.initialize();
+            plotter.move_to(5.0, 5.0);
+            plotter.draw(20.0, 6.7);
+            assert_eq!(plotter.position(), (20.0, 6.7));
+            plotter.finalize();
+        }
+        drop(plotters);
+        let hpgl = std::fs::read_to_string(&hpgl_path).unwrap();
+        std::fs::remove_file(&hpgl_path).unwrap();
+        assert_eq!(hpgl, "IN;PU25,25;PU224,225;PD821,293;PU0,0;SP0;");
+    }
+
+    #[test]
+    fn position_is_where_the_last_draw_ended() {
+        let hpgl_path = std::env::temp_dir().join("rplotter_test_position.plt");
+        let mut plotters: Vec<Box<dyn Plottable>> = vec![
+            Box::new(MockPlotter::new(0.0, 0.0, 50.0, 50.0)),
+            Box::new(PlotBuilder::new(0.0, 0.0, 50.0, 50.0)),
+            Box::new(GcodePlotter::new(Path::new("unused.gcode"), 0.0, 0.0, 50.0, 50.0, GcodeSettings::default())),
+            Box::new(SvgPlotter::new(Path::new("unused.svg"), 0.0, 0.0, 50.0, 50.0)),
+            Box::new(USCutter::to_file(&hpgl_path, 0.0, 0.0, 50.0, 50.0).unwrap()),
+        ];
+        for plotter in plotters.iter_mut() {
+            assert_eq!(plotter.position(), (0.0, 0.0));
+            plotter.draw(10.0, 5.0);
+            assert_eq!(plotter.position(), (10.0, 5.0));
+            plotter.move_relative(-2.0, 1.0);
+            assert_eq!(plotter.position(), (8.0, 6.0));
+        }
+        drop(plotters);
+        std::fs::remove_file(&hpgl_path).unwrap();
+    }
 }
@@ -0,0 +1,45 @@
+//! rng module contains a small seedable pseudo-random number generator, so generative plots
+//! can be reproduced exactly by reusing the same seed.
+//! It is not suitable for anything security related.
+//!
+
+/// SplitMix64 generator.  See https://prng.di.unimi.it/splitmix64.c
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator.  The same seed always produces the same sequence.
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Next value, uniformly distributed over all u64.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Next value, uniformly distributed in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Next value, uniformly distributed in [low, high).
+    pub fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    /// Next value, uniformly distributed in 0..n.  n must be greater than zero.
+    pub fn below(&mut self, n: u32) -> u32 {
+        (self.next_u64() % n as u64) as u32
+    }
+
+    /// A coin flip.
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+}
@@ -7,6 +7,8 @@ use std::f64::consts::PI;
 use crate::plottable::Plottable;
 
 const STEPS: i32 = 40; // Steps in one rotation of the rolling circle.
+const GEAR_STEPS: usize = 8;    // Segments in each tooth flank, tip and root of a gear.
+const MIN_GEAR_TEETH: u32 = 6; // Fewer teeth than this don't make a working gear.
 
 /// Circle that rolls along other curves and generates the roulette curves.
 /// Fields are in mm.
@@ -15,14 +17,15 @@ struct Roller {
     pen_radius: f64,
 }
 
-struct Translator {
-    centerx_mm: f64,
-    centery_mm: f64,
-    rot_rad: f64,
+/// Rotates a curve computed around the origin by rot_rad and moves it to (centerx_mm, centery_mm).
+pub(crate) struct Translator {
+    pub(crate) centerx_mm: f64,
+    pub(crate) centery_mm: f64,
+    pub(crate) rot_rad: f64,
 }
 
 impl Translator {
-    fn translate(&self, inputx: f64, inputy: f64) -> (f64, f64) {
+    pub(crate) fn translate(&self, inputx: f64, inputy: f64) -> (f64, f64) {
         let mut x = inputx * self.rot_rad.cos() - inputy * self.rot_rad.sin();
         let mut y = inputx * self.rot_rad.sin() + inputy * self.rot_rad.cos();
         x += self.centerx_mm;
@@ -44,10 +47,30 @@ impl Translator {
 ///
 pub fn full_hypotrochoid(plotter: &mut impl Plottable, rolling_radius_mm: f64, pen_radius_mm: f64,
                          inner: i32, outer: i32, centerx_mm: f64, centery_mm: f64, rot_rad: f64 ) {
+    full_hypotrochoid_with_steps(plotter, rolling_radius_mm, pen_radius_mm, inner, outer,
+                                 centerx_mm, centery_mm, rot_rad, STEPS);
+}
+
+/// Like full_hypotrochoid(), with the resolution given: steps segments for each rotation of the
+/// rolling circle, at least 3.  full_hypotrochoid() uses STEPS (40), which is visibly faceted on
+/// curves around 30 mm across and more than needed on small ones.
+///
+/// # Examples
+///
+/// ```
+/// roulette::full_hypotrochoid_with_steps(plotter, 30.0, 16.5, 5, 6, 0.0, 0.0, 0.0, 120);
+/// ```
+///
+pub fn full_hypotrochoid_with_steps(plotter: &mut impl Plottable, rolling_radius_mm: f64, pen_radius_mm: f64,
+                                    inner: i32, outer: i32, centerx_mm: f64, centery_mm: f64, rot_rad: f64,
+                                    steps: i32) {
     // Error checking.
     if inner > outer {
         panic!("Parameter `inner` must be greater than `outer`.")
     }
+    if steps < 3 {
+        panic!("Parameter `steps` must be at least 3.")
+    }
     // Setup.
     let ratio: f64 = inner as f64 / outer as f64;
     let outer_mm = rolling_radius_mm / ratio;
@@ -60,12 +83,303 @@ pub fn full_hypotrochoid(plotter: &mut impl Plottable, rolling_radius_mm: f64, p
     // Plotting.
     let (x, y ) = trans.translate(plot_radius, 0.0);
     plotter.move_to(x, y);
-    for i in 0 .. (inner * STEPS + 1) { // Add one to get a complete curve.
-        let t = 2.0 * PI * i as f64 / STEPS as f64;
+    for i in 0 .. (inner * steps + 1) { // Add one to get a complete curve.
+        let t = 2.0 * PI * i as f64 / steps as f64;
         let x = outer_mm * ((1.0 - ratio) * t.cos() + pen2outer * ((1.0 - ratio) / ratio * t).cos() );
         let y = outer_mm * ((1.0 - ratio) * t.sin() - pen2outer * ((1.0 - ratio) / ratio * t).sin() );
         let (x, y) = trans.translate(x, y);
         plotter.draw(x, y);
     }
 
-}
\ No newline at end of file
+}
+
+/// Generate a full hypocycloid: a hypotrochoid with the pen on the rim of the rolling circle,
+/// so the curve's cusps touch the outer circle.  Same as full_hypotrochoid() with
+/// pen_radius_mm = rolling_radius_mm.
+///
+/// plotter: device to plot to.
+/// rolling_radius: radius of rolling circle in mm.
+/// inner, outer: integers that allow you to set the relative sizes of the two circles.
+///
+/// # Examples
+///
+/// ```
+/// roulette::hypocycloid(plotter, 30.0, 5, 6, 0.0, 0.0, 0.0); // 6 cusps on a 36 mm circle.
+/// ```
+///
+pub fn hypocycloid(plotter: &mut impl Plottable, rolling_radius_mm: f64, inner: i32, outer: i32,
+                   centerx_mm: f64, centery_mm: f64, rot_rad: f64) {
+    full_hypotrochoid(plotter, rolling_radius_mm, rolling_radius_mm, inner, outer, centerx_mm, centery_mm, rot_rad);
+}
+
+/// Parameters of a hypotrochoid, as used by full_hypotrochoid(), gathered together so that a
+/// series of related curves can be described by a starting value plus a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HypotrochoidParams {
+    pub rolling_radius_mm: f64,
+    pub pen_radius_mm: f64,
+    pub inner: i32,
+    pub outer: i32,
+    pub rot_rad: f64,
+}
+
+/// Draw a layered series of hypotrochoids sharing a center, changing each curve's parameters
+/// by param_delta and stepping through the palette, one color per curve.  With small deltas
+/// the layers shade from one color to the next.
+///
+/// plotter: device to plot to.
+/// base_params: parameters of the first curve.
+/// param_delta: added to the parameters once per step (use 0 to hold a parameter fixed).
+/// steps: number of curves.
+/// palette: pen colors, used in order and repeated if shorter than steps.  If empty, the pen is never changed.
+///
+pub fn roulette_gradient(plotter: &mut impl Plottable, base_params: HypotrochoidParams,
+                         param_delta: HypotrochoidParams, steps: i32, palette: &[&str],
+                         centerx_mm: f64, centery_mm: f64) {
+    let mut p = base_params;
+    for i in 0 .. steps {
+        if !palette.is_empty() {
+            plotter.change_color(palette[i as usize % palette.len()]);
+        }
+        full_hypotrochoid(plotter, p.rolling_radius_mm, p.pen_radius_mm, p.inner, p.outer,
+                          centerx_mm, centery_mm, p.rot_rad);
+        p.rolling_radius_mm += param_delta.rolling_radius_mm;
+        p.pen_radius_mm += param_delta.pen_radius_mm;
+        p.inner += param_delta.inner;
+        p.outer += param_delta.outer;
+        p.rot_rad += param_delta.rot_rad;
+    }
+}
+
+/// Draw one frame of a rotating hypotrochoid animation: the curve rotated to
+/// phase_start_rad + phase_step_rad * frame (in place of params.rot_rad).
+/// Plot frames 0, 1, 2, ... on fresh sheets and photograph them in order.
+/// A hypotrochoid with `outer` lobes repeats after 2π / outer, so a step of 2π / (outer * N)
+/// gives a seamless loop of N frames.
+pub fn hypotrochoid_phase_series(plotter: &mut impl Plottable, params: HypotrochoidParams, phase_start_rad: f64,
+                                 phase_step_rad: f64, frame: u32, centerx_mm: f64, centery_mm: f64) {
+    let rot_rad = phase_start_rad + phase_step_rad * frame as f64;
+    full_hypotrochoid(plotter, params.rolling_radius_mm, params.pen_radius_mm, params.inner, params.outer,
+                      centerx_mm, centery_mm, rot_rad);
+}
+
+/// Generate a guilloché band: a set of circles whose radius is modulated by a sine wave,
+/// each one shifted in phase from the last so the curves weave over and under each other
+/// (like the fine line work on banknotes).
+///
+/// plotter: device to plot to.
+/// radius_mm: unmodulated radius of each curve in mm.
+/// amplitude_mm: peak deviation from radius_mm in mm.
+/// frequency: number of waves around one circuit of the circle.
+/// phase_step_rad: phase shift between successive curves, in radians.
+/// count: number of curves in the band.
+///
+/// A phase step of 2π / (frequency * count) spreads the curves evenly across one wave.
+///
+pub fn guilloche(plotter: &mut impl Plottable, radius_mm: f64, amplitude_mm: f64, frequency: i32,
+                 phase_step_rad: f64, count: i32, centerx_mm: f64, centery_mm: f64, rot_rad: f64) {
+    // Error checking.
+    if frequency < 1 {
+        panic!("Parameter `frequency` must be at least 1.")
+    }
+    if amplitude_mm >= radius_mm {
+        panic!("Parameter `amplitude_mm` must be less than `radius_mm`.")
+    }
+    // Setup.
+    let steps = frequency * STEPS; // STEPS per wave so that high frequencies stay smooth.
+    let trans = Translator {centerx_mm, centery_mm, rot_rad};
+
+    // Plotting.
+    for k in 0 .. count {
+        let phase = phase_step_rad * k as f64;
+        for i in 0 .. (steps + 1) { // Add one to get a complete curve.
+            let t = 2.0 * PI * i as f64 / steps as f64;
+            let r = radius_mm + amplitude_mm * (frequency as f64 * t + phase).sin();
+            let (x, y) = trans.translate(r * t.cos(), r * t.sin());
+            if i == 0 {
+                plotter.move_to(x, y);
+            } else {
+                plotter.draw(x, y);
+            }
+        }
+    }
+}
+
+/// Ready-made wallpapers of tiled hypotrochoids, for roulette_wallpaper().
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallpaperPreset {
+    /// Three nested layers (cyan, green, black) in a hexagonal layout; the outer stars touch.
+    Rosettes,
+    /// Single black six-pointed stars in a hexagonal layout, touching point to point.
+    Stars,
+    /// Blue four-lobed loops in offset rows, like courses of bricks.
+    Bricks,
+}
+
+/// One hypotrochoid of a wallpaper cell, at the preset's base scale.
+struct Layer {
+    color: &'static str,
+    rolling_radius_mm: f64,
+    pen_radius_mm: f64,
+    inner: i32,
+    outer: i32,
+}
+
+impl Layer {
+    /// Max extent of the curve from its center, as in full_hypotrochoid().
+    fn plot_radius(&self) -> f64 {
+        self.rolling_radius_mm * self.outer as f64 / self.inner as f64 - self.rolling_radius_mm + self.pen_radius_mm
+    }
+}
+
+impl WallpaperPreset {
+    /// The preset's layers, its cell width at base scale, its row height as a fraction of the
+    /// cell width, and how far each row is shifted as a fraction of the cell width.
+    fn layout(&self) -> (Vec<Layer>, f64, f64, f64) {
+        let hex_row = 3.0f64.sqrt() / 2.0;
+        match self {
+            WallpaperPreset::Rosettes => (vec![
+                Layer {color: "cyan", rolling_radius_mm: 5.7, pen_radius_mm: 3.8, inner: 7, outer: 12},
+                Layer {color: "green", rolling_radius_mm: 10.0, pen_radius_mm: 5.5, inner: 5, outer: 6},
+                Layer {color: "black", rolling_radius_mm: 10.0, pen_radius_mm: 10.0, inner: 5, outer: 6},
+            ], 24.0, hex_row, 0.5),
+            WallpaperPreset::Stars => (vec![
+                Layer {color: "black", rolling_radius_mm: 10.0, pen_radius_mm: 10.0, inner: 5, outer: 6},
+            ], 24.0, hex_row, 0.5),
+            WallpaperPreset::Bricks => (vec![
+                Layer {color: "blue", rolling_radius_mm: 6.0, pen_radius_mm: 4.0, inner: 3, outer: 4},
+            ], 14.0, 12.0 / 14.0, 0.5),
+        }
+    }
+}
+
+/// Fill bounds with a tiled roulette pattern.  The preset is scaled so that a whole number of
+/// cells spans the width of the bounds, about as many as at its base scale, and as many rows
+/// as fit are centered vertically.  Only cells whose curves fit entirely within the bounds are drawn,
+/// one color layer at a time so each pen is loaded once.
+///
+/// plotter: device to plot to.
+/// bounds: area to fill, as (llx_mm, lly_mm, urx_mm, ury_mm).
+/// preset: which pattern.
+///
+pub fn roulette_wallpaper(plotter: &mut impl Plottable, bounds: (f64, f64, f64, f64), preset: WallpaperPreset) {
+    let (llx, lly, urx, ury) = bounds;
+    let (layers, base_cell_mm, row_fraction, shift_fraction) = preset.layout();
+    let cells_across = (((urx - llx) / base_cell_mm).round() as i32).max(1);
+    let cell_mm = (urx - llx) / cells_across as f64;
+    let scale = cell_mm / base_cell_mm;
+    let row_mm = cell_mm * row_fraction;
+    let radius_mm = layers.iter().map(|l| l.plot_radius() * scale).fold(0.0, f64::max);
+    let rows = (((ury - lly - 2.0 * radius_mm) / row_mm).floor() as i32 + 1).max(1);
+    // Corner of the bottom row of cells, placing the rows' centers evenly about the middle.
+    let offset_y_mm = (lly + ury) / 2.0 - (rows - 1) as f64 * row_mm / 2.0 - row_mm / 2.0;
+
+    for layer in &layers {
+        plotter.change_color(layer.color);
+        let radius = layer.plot_radius() * scale;
+        crate::tiling::tile_with_offset(plotter, cell_mm, row_mm, llx, offset_y_mm, cell_mm * shift_fraction,
+                                        bounds, |p, x, y| {
+            let (cx, cy) = (x + cell_mm / 2.0, y + row_mm / 2.0);
+            // Skip cells whose curve would cross the bounds; 1e-6 mm allows for rounding.
+            let margin = radius - 1e-6;
+            if cx - margin >= llx && cx + margin <= urx && cy - margin >= lly && cy + margin <= ury {
+                full_hypotrochoid(p, layer.rolling_radius_mm * scale, layer.pen_radius_mm * scale,
+                                  layer.inner, layer.outer, cx, cy, 0.0);
+            }
+        });
+    }
+}
+
+/// Draw the outline of an involute spur gear as one closed path: the tooth flanks are involutes
+/// of the base circle (the curve traced by the end of a taut string unwound from it, a roulette
+/// of a line rolling on the circle), joined by arcs of the tip and root circles.
+/// https://en.wikipedia.org/wiki/Involute_gear
+///
+/// plotter: device to plot to.
+/// teeth: number of teeth, at least MIN_GEAR_TEETH.
+/// module_mm: size of the teeth: the pitch circle diameter divided by the number of teeth.
+///            Gears that mesh must have the same module.
+/// pressure_angle_rad: angle of the tooth flanks at the pitch circle; 20° (0.349) is standard.
+/// cx_mm, cy_mm: center of the gear.
+/// rot_rad: rotation; at 0 the first tooth points along +x.
+///
+/// Proportions are the standard ones: addendum 1 module, dedendum 1.25 modules.  The fillets at
+/// the root are left square, and gears with fewer than about 17 teeth at 20° would be undercut
+/// when cut, which is not shown.
+///
+pub fn gear(plotter: &mut impl Plottable, teeth: u32, module_mm: f64, pressure_angle_rad: f64,
+            cx_mm: f64, cy_mm: f64, rot_rad: f64) {
+    // Error checking.
+    if teeth < MIN_GEAR_TEETH {
+        panic!("Parameter `teeth` must be at least {}.", MIN_GEAR_TEETH)
+    }
+    if pressure_angle_rad <= 0.0 || pressure_angle_rad >= PI / 4.0 {
+        panic!("Parameter `pressure_angle_rad` must be between 0 and π/4.")
+    }
+    // Setup.
+    let z = teeth as f64;
+    let pitch_r = module_mm * z / 2.0;
+    let base_r = pitch_r * pressure_angle_rad.cos();
+    let tip_r = pitch_r + module_mm;
+    let root_r = pitch_r - 1.25 * module_mm;
+    let flank_start_r = base_r.max(root_r); // No involute inside the base circle.
+    let involute = |a: f64| a.tan() - a;
+    // Half the angular width of a tooth at radius r: half a tooth pitch at the pitch circle,
+    // narrowing towards the tip along the involute.
+    let half_width = |r: f64| PI / (2.0 * z) + involute(pressure_angle_rad) - involute((base_r / r).min(1.0).acos());
+    if half_width(tip_r) <= 0.0 {
+        panic!("The teeth come to a point before the tip circle; use more teeth or a smaller pressure angle.")
+    }
+    let trans = Translator {centerx_mm: cx_mm, centery_mm: cy_mm, rot_rad};
+    let polar = |r: f64, angle: f64| trans.translate(r * angle.cos(), r * angle.sin());
+
+    // Plotting, counterclockwise: for each tooth, up the right flank, across the tip, down the
+    // left flank, then along the root to the next tooth.
+    let (x, y) = polar(root_r, -half_width(flank_start_r));
+    plotter.move_to(x, y);
+    for k in 0 .. teeth {
+        let center = 2.0 * PI * k as f64 / z;
+        let mut points = Vec::with_capacity(4 * GEAR_STEPS + 4);
+        for i in 0 .. (GEAR_STEPS + 1) {
+            let r = flank_start_r + (tip_r - flank_start_r) * i as f64 / GEAR_STEPS as f64;
+            points.push((r, center - half_width(r)));
+        }
+        for i in 1 .. GEAR_STEPS {
+            let t = i as f64 / GEAR_STEPS as f64;
+            points.push((tip_r, center - half_width(tip_r) + 2.0 * half_width(tip_r) * t));
+        }
+        for i in (0 .. (GEAR_STEPS + 1)).rev() {
+            let r = flank_start_r + (tip_r - flank_start_r) * i as f64 / GEAR_STEPS as f64;
+            points.push((r, center + half_width(r)));
+        }
+        points.push((root_r, center + half_width(flank_start_r)));
+        let next_start = center + 2.0 * PI / z - half_width(flank_start_r);
+        let root_start = center + half_width(flank_start_r);
+        for i in 1 .. (GEAR_STEPS + 1) {
+            let t = i as f64 / GEAR_STEPS as f64;
+            points.push((root_r, root_start + (next_start - root_start) * t));
+        }
+        for (r, angle) in points {
+            let (x, y) = polar(r, angle);
+            plotter.draw(x, y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockPlotter;
+
+    #[test]
+    fn hypocycloid_starts_and_ends_on_the_outer_circle() {
+        let mut mock = MockPlotter::new(-50.0, -50.0, 50.0, 50.0);
+        hypocycloid(&mut mock, 30.0, 5, 6, 5.0, -3.0, 0.4);
+        let points = mock.points();
+        let outer_mm = 30.0 * 6.0 / 5.0;
+        let (first, last) = (points[0], points[points.len() - 1]);
+        assert!(((first.0 - 5.0).hypot(first.1 + 3.0) - outer_mm).abs() < 1e-9, "{:?}", first);
+        assert!(((last.0 - 5.0).hypot(last.1 + 3.0) - outer_mm).abs() < 1e-9, "{:?}", last);
+        assert!((first.0 - last.0).hypot(first.1 - last.1) < 1e-9);
+    }
+}
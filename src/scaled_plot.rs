@@ -0,0 +1,152 @@
+//! scaled_plot module contains the ScaledPlotter struct, a wrapper around another Plottable
+//! that multiplies every coordinate by a scale factor before passing it on.  Reuse a plot
+//! routine at another size without touching the radii written into it.
+//!
+
+use std::io;
+
+use crate::plottable::Plottable;
+
+pub struct ScaledPlotter<'a> {
+    inner: &'a mut dyn Plottable,
+    sx: f64, // Scale factors applied to x and y.
+    sy: f64,
+}
+
+impl<'a> ScaledPlotter<'a> {
+    /// Wrap a plotter so that the point (x, y) is plotted at (sx * x, sy * y).  Scaling is about
+    /// the origin; a negative factor mirrors the plot across that axis.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Double size.
+    /// let mut scaled = ScaledPlotter::new(&mut plotter, 2.0, 2.0);
+    /// roulette::full_hypotrochoid(&mut scaled, 30.0, 16.5, 5, 6, 0.0, 0.0, 0.0);
+    /// ```
+    ///
+    pub fn new(inner: &'a mut dyn Plottable, sx: f64, sy: f64) -> ScaledPlotter<'a> {
+        if sx == 0.0 || sy == 0.0 {
+            panic!("Parameters `sx` and `sy` must be nonzero.");
+        }
+        ScaledPlotter { inner, sx, sy }
+    }
+}
+
+impl<'a> Plottable for ScaledPlotter<'a> {
+
+    fn initialize(&mut self) {
+        self.inner.initialize();
+    }
+
+    fn finalize(&mut self) {
+        self.inner.finalize();
+    }
+
+    /// Draw a straight line from present position to absolute position (destx_mm, desty_mm), scaled.
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.inner.draw(self.sx * destx_mm, self.sy * desty_mm);
+    }
+
+    /// Move pen without drawing to absolute position (destx_mm, desty_mm), scaled.
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.inner.move_to(self.sx * destx_mm, self.sy * desty_mm);
+    }
+
+    /// Draw from present position (dx, dy) mm, scaled.
+    /// Returns the new (unscaled) position of the pen.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.inner.draw_relative(self.sx * dx_mm, self.sy * dy_mm);
+        self.position()
+    }
+
+    /// Move the pen without drawing from present position (dx, dy) mm, scaled.
+    /// Returns the new (unscaled) position of the pen.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.inner.move_relative(self.sx * dx_mm, self.sy * dy_mm);
+        self.position()
+    }
+
+    fn pen_up(&mut self) {
+        self.inner.pen_up();
+    }
+
+    fn pen_down(&mut self) {
+        self.inner.pen_down();
+    }
+
+    fn change_color(&mut self, color_name: &str) {
+        self.inner.change_color(color_name);
+    }
+
+    /// The wrapped plotter's plot rectangle, unscaled, so plots that size themselves to
+    /// bounds() still fill it.
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        let (llx_mm, lly_mm, urx_mm, ury_mm) = self.inner.bounds();
+        let (x0, x1) = (llx_mm / self.sx, urx_mm / self.sx);
+        let (y0, y1) = (lly_mm / self.sy, ury_mm / self.sy);
+        (x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))
+    }
+
+    /// Present position of the pen, unscaled.
+    fn position(&self) -> (f64, f64) {
+        let (x, y) = self.inner.position();
+        (x / self.sx, y / self.sy)
+    }
+
+    fn check_status(&self) -> io::Result<()> {
+        self.inner.check_status()
+    }
+
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.inner.set_velocity(cm_per_sec);
+    }
+
+    fn home(&mut self) {
+        self.inner.home();
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush();
+    }
+
+    /// Draw the polyline through points, scaled, as one path on the wrapped plotter.
+    fn draw_path(&mut self, points: &[(f64, f64)]) {
+        let scaled: Vec<(f64, f64)> = points.iter().map(|&(x, y)| (self.sx * x, self.sy * y)).collect();
+        self.inner.draw_path(&scaled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roulette;
+    use crate::testing::{MockPlotter, PlotCommand};
+
+    #[test]
+    fn hypotrochoid_is_drawn_at_double_size() {
+        let mut mock = MockPlotter::new(-80.0, -80.0, 80.0, 80.0);
+        roulette::full_hypotrochoid(&mut ScaledPlotter::new(&mut mock, 2.0, 2.0), 30.0, 16.5, 5, 6, 0.0, 0.0, 0.0);
+        assert_eq!(mock.commands()[0], PlotCommand::MoveTo(45.0, 0.0)); // Plot radius 22.5 mm, doubled.
+
+        let mut unscaled = MockPlotter::new(-80.0, -80.0, 80.0, 80.0);
+        roulette::full_hypotrochoid(&mut unscaled, 30.0, 16.5, 5, 6, 0.0, 0.0, 0.0);
+        for (p, q) in mock.points().iter().zip(unscaled.points().iter()) {
+            assert_eq!(*p, (2.0 * q.0, 2.0 * q.1));
+        }
+    }
+
+    #[test]
+    fn relative_moves_are_scaled() {
+        let mut mock = MockPlotter::new(-80.0, -80.0, 80.0, 80.0);
+        {
+            let mut scaled = ScaledPlotter::new(&mut mock, 2.0, 3.0);
+            scaled.move_to(1.0, 1.0);
+            assert_eq!(scaled.move_relative(1.0, 2.0), (2.0, 3.0));
+            assert_eq!(scaled.draw_relative(-1.0, 1.0), (1.0, 4.0));
+            assert_eq!(scaled.bounds(), (-40.0, -80.0 / 3.0, 40.0, 80.0 / 3.0));
+        }
+        assert_eq!(mock.commands(), &[PlotCommand::MoveTo(2.0, 3.0), PlotCommand::MoveTo(4.0, 9.0),
+                                      PlotCommand::Draw(2.0, 12.0)]);
+    }
+}
@@ -0,0 +1,121 @@
+//! script module runs plots written in a small text command language, so designs can be kept
+//! in data files instead of compiled in.  One command per line:
+//!
+//! ```text
+//! M x y     move to (x, y) without drawing
+//! L x y     draw a line to (x, y)
+//! R dx dy   go (dx, dy) from the present position, drawing if the pen is down
+//! U         raise the pen
+//! D         lower the pen (for R)
+//! C name    change to the named pen color
+//! # ...     comment
+//! ```
+//!
+//! Coordinates are in mm.  Blank lines are ignored.
+//!
+
+use std::error::Error;
+use std::fmt;
+
+use crate::plottable::Plottable;
+
+/// A syntax error in a plot script, with the (1-based) line it is on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ScriptError {}
+
+enum Command {
+    Move(f64, f64),
+    Line(f64, f64),
+    Relative(f64, f64),
+    Up,
+    Down,
+    Color(String),
+}
+
+/// Run a plot script.  The whole script is checked first, so a syntax error stops it before
+/// anything is plotted.  Does not call initialize() or finalize().
+pub fn run_script(plotter: &mut impl Plottable, script: &str) -> Result<(), ScriptError> {
+    let commands = parse_script(script)?;
+    let mut pen_is_down = false;
+    for command in commands {
+        match command {
+            Command::Move(x, y) => {
+                plotter.move_to(x, y);
+                pen_is_down = false;
+            }
+            Command::Line(x, y) => {
+                plotter.draw(x, y);
+                pen_is_down = true;
+            }
+            Command::Relative(dx, dy) => {
+                if pen_is_down {
+                    plotter.draw_relative(dx, dy);
+                } else {
+                    plotter.move_relative(dx, dy);
+                }
+            }
+            Command::Up => {
+                plotter.pen_up();
+                pen_is_down = false;
+            }
+            Command::Down => pen_is_down = true,
+            Command::Color(name) => plotter.change_color(&name),
+        }
+    }
+    Ok(())
+}
+
+fn parse_script(script: &str) -> Result<Vec<Command>, ScriptError> {
+    let mut commands = Vec::new();
+    for (i, text) in script.lines().enumerate() {
+        let line = i + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| ScriptError { line, message };
+        let mut words = text.split_whitespace();
+        let op = words.next().unwrap_or("");
+        let args: Vec<&str> = words.collect();
+        let command = match op.to_ascii_uppercase().as_str() {
+            "M" | "L" | "R" => {
+                if args.len() != 2 {
+                    return Err(error(format!("`{}` takes 2 numbers, found {}", op, args.len())));
+                }
+                let x: f64 = args[0].parse().map_err(|_| error(format!("`{}` is not a number", args[0])))?;
+                let y: f64 = args[1].parse().map_err(|_| error(format!("`{}` is not a number", args[1])))?;
+                match op.to_ascii_uppercase().as_str() {
+                    "M" => Command::Move(x, y),
+                    "L" => Command::Line(x, y),
+                    _ => Command::Relative(x, y),
+                }
+            }
+            "U" | "D" => {
+                if !args.is_empty() {
+                    return Err(error(format!("`{}` takes no arguments", op)));
+                }
+                if op.eq_ignore_ascii_case("U") { Command::Up } else { Command::Down }
+            }
+            "C" => {
+                if args.len() != 1 {
+                    return Err(error("`C` takes one color name".to_string()));
+                }
+                Command::Color(args[0].to_string())
+            }
+            _ => return Err(error(format!("unknown command `{}`", op))),
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
@@ -0,0 +1,372 @@
+//! The shapes module contains composite figures built from the basic Plottable operations.
+//!
+
+use std::f64::consts::PI;
+use crate::geometry::convex_hull;
+use crate::plottable::Plottable;
+use crate::rng::Rng;
+use crate::text::{self, Align};
+
+/// Draw a clock face showing hour:minute: the outer circle, twelve hour ticks (longer at
+/// 12, 3, 6 and 9), and the hour and minute hands.
+///
+/// plotter: device to plot to.
+/// cx_mm, cy_mm: center of the clock.
+/// radius_mm: radius of the outer circle.
+/// hour: 0-23, minute: 0-59.  The hour hand moves between hours as the minutes pass.
+///
+pub fn clock_face(plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64, radius_mm: f64, hour: u32, minute: u32) {
+    // Error checking.
+    if hour > 23 || minute > 59 {
+        panic!("Parameters `hour` and `minute` must be a valid time of day.")
+    }
+    // Angle for a fraction of a full turn, clockwise from 12 o'clock.
+    let angle = |turns: f64| PI / 2.0 - 2.0 * PI * turns;
+    let mut line = |angle: f64, from_mm: f64, to_mm: f64| {
+        plotter.move_to(cx_mm + from_mm * angle.cos(), cy_mm + from_mm * angle.sin());
+        plotter.draw(cx_mm + to_mm * angle.cos(), cy_mm + to_mm * angle.sin());
+    };
+
+    // Hour ticks.
+    for i in 0 .. 12 {
+        let inner = if i % 3 == 0 { 0.75 } else { 0.85 };
+        line(angle(i as f64 / 12.0), inner * radius_mm, radius_mm);
+    }
+    // Hands.
+    let hours = (hour % 12) as f64 + minute as f64 / 60.0;
+    line(angle(hours / 12.0), 0.0, 0.5 * radius_mm);
+    line(angle(minute as f64 / 60.0), 0.0, 0.8 * radius_mm);
+
+    circle_at(plotter, cx_mm, cy_mm, radius_mm);
+}
+
+/// Draw a circle centered at (cx_mm, cy_mm), with the plotter's circle(), so a USCutter draws
+/// it natively.  The pen is left at the center.
+pub fn circle_at(plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64, radius_mm: f64) {
+    plotter.move_to(cx_mm, cy_mm);
+    plotter.circle(radius_mm);
+}
+
+/// Draw a regular polygon with num_sides sides, its vertices on the circle of radius_mm around
+/// (cx_mm, cy_mm), the first at rot_rad (0 = along +x, positive is CCW).
+///
+/// # Examples
+///
+/// ```
+/// shapes::regular_polygon(plotter, 6, 20.0, 0.0, 0.0, 0.0); // Hexagon with a vertex at (20, 0).
+/// ```
+///
+pub fn regular_polygon(plotter: &mut impl Plottable, num_sides: u32, radius_mm: f64, cx_mm: f64, cy_mm: f64,
+                       rot_rad: f64) {
+    star_polygon(plotter, num_sides, 1, radius_mm, cx_mm, cy_mm, rot_rad);
+}
+
+/// Draw the star polygon {points/step}: points vertices evenly spaced on the circle of radius_mm
+/// around (cx_mm, cy_mm), the first at rot_rad, with a line from each to the one step further round.
+/// E.g. {5/2} is a pentagram.  When points and step have a common factor the star is several
+/// separate polygons, e.g. {6/2} is two triangles, and each is drawn in turn.
+///
+/// plotter: device to plot to.
+/// points: number of vertices, at least 3.
+/// step: vertices to go round for each line, from 1 (a regular polygon) to points - 1.
+///
+pub fn star_polygon(plotter: &mut impl Plottable, points: u32, step: u32, radius_mm: f64, cx_mm: f64, cy_mm: f64,
+                    rot_rad: f64) {
+    // Error checking.
+    if points < 3 {
+        panic!("Parameter `points` must be at least 3.")
+    }
+    if step < 1 || step >= points {
+        panic!("Parameter `step` must be from 1 to `points` - 1.")
+    }
+    let vertex = |i: u32| {
+        let angle = rot_rad + 2.0 * PI * (i % points) as f64 / points as f64;
+        (cx_mm + radius_mm * angle.cos(), cy_mm + radius_mm * angle.sin())
+    };
+    // The number of separate polygons is the greatest common divisor of points and step.
+    let (mut a, mut b) = (points, step);
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    let polygons = a;
+    for start in 0 .. polygons {
+        let path: Vec<(f64, f64)> = (0 ..= points / polygons).map(|j| vertex(start + j * step)).collect();
+        plotter.draw_path(&path);
+    }
+}
+
+/// Draw a sunburst: count lines radiating from (cx_mm, cy_mm), evenly spaced around it, each from
+/// inner_radius_mm out to outer_radius_mm.  The first line is at rot_rad (0 = pointing along +x,
+/// positive is CCW).  Alternate lines are drawn outwards and inwards, so the pen moves only
+/// along the rim between them.
+pub fn sunburst(plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64, inner_radius_mm: f64,
+                outer_radius_mm: f64, count: u32, rot_rad: f64) {
+    for i in 0 .. count {
+        let angle = rot_rad + 2.0 * PI * i as f64 / count as f64;
+        let (from_mm, to_mm) = if i % 2 == 0 {
+            (inner_radius_mm, outer_radius_mm)
+        } else {
+            (outer_radius_mm, inner_radius_mm)
+        };
+        plotter.move_to(cx_mm + from_mm * angle.cos(), cy_mm + from_mm * angle.sin());
+        plotter.draw(cx_mm + to_mm * angle.cos(), cy_mm + to_mm * angle.sin());
+    }
+}
+
+/// Draw a compass rose: the outer circle and eight pointed rays, long ones for north, east,
+/// south and west and short ones between.  Each ray is a kite with a ridge line down its middle.
+///
+/// plotter: device to plot to.
+/// cx_mm, cy_mm: center of the rose.
+/// radius_mm: radius of the outer circle.  The long rays reach 90% of it.
+/// rot_rad: rotation; at 0 north points up (+y), positive is CCW.
+/// labels: whether to write N, E, S and W outside the circle, turned with the rose.
+///
+pub fn compass_rose(plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64, radius_mm: f64, rot_rad: f64, labels: bool) {
+    let point = |r: f64, angle: f64| (cx_mm + r * angle.cos(), cy_mm + r * angle.sin());
+    // Intermediate rays first, so the long rays are drawn over them.
+    for &primary in &[false, true] {
+        for i in 0 .. 8 {
+            if (i % 2 == 0) != primary {
+                continue;
+            }
+            let angle = PI / 2.0 + rot_rad - PI / 4.0 * i as f64; // Clockwise from north.
+            let (length, base) = if primary { (0.9, 0.15) } else { (0.55, 0.1) };
+            let (tip, left, right) = (point(length * radius_mm, angle), point(base * radius_mm, angle + PI / 8.0),
+                                      point(base * radius_mm, angle - PI / 8.0));
+            plotter.move_to(cx_mm, cy_mm);
+            for &(x, y) in &[left, tip, right, (cx_mm, cy_mm), tip] {
+                plotter.draw(x, y);
+            }
+        }
+    }
+    circle_at(plotter, cx_mm, cy_mm, radius_mm);
+
+    if labels {
+        let height_mm = radius_mm / 8.0;
+        let (sin, cos) = rot_rad.sin_cos();
+        for (i, letter) in ["N", "E", "S", "W"].iter().enumerate() {
+            let angle = PI / 2.0 + rot_rad - PI / 2.0 * i as f64;
+            // Center of the letter, then its baseline half a letter height below that.
+            let (x, y) = point(radius_mm + height_mm, angle);
+            text::draw_text(plotter, letter, x + sin * height_mm / 2.0, y - cos * height_mm / 2.0,
+                            height_mm, rot_rad, Align::Center);
+        }
+    }
+}
+
+const MIN_GASKET_RADIUS_MM: f64 = 0.1; // Smaller circles are just a blob of ink.
+
+/// A circle stored by curvature k = 1/r (negative for the enclosing circle) and k times its
+/// center, which is the form Descartes' circle theorem works in.
+#[derive(Clone, Copy)]
+struct Disc {
+    k: f64,
+    kx: f64,
+    ky: f64,
+}
+
+impl Disc {
+    fn new(radius_mm: f64, x_mm: f64, y_mm: f64) -> Disc {
+        let k = 1.0 / radius_mm;
+        Disc { k, kx: k * x_mm, ky: k * y_mm }
+    }
+
+    /// The other circle tangent to a, b and c, given one of them is d.  By Descartes' theorem
+    /// the two solutions sum to 2(a + b + c), in both curvature and curvature times center.
+    fn reflect(a: Disc, b: Disc, c: Disc, d: Disc) -> Disc {
+        Disc {
+            k: 2.0 * (a.k + b.k + c.k) - d.k,
+            kx: 2.0 * (a.kx + b.kx + c.kx) - d.kx,
+            ky: 2.0 * (a.ky + b.ky + c.ky) - d.ky,
+        }
+    }
+
+    fn draw(&self, plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64) {
+        circle_at(plotter, cx_mm + self.kx / self.k, cy_mm + self.ky / self.k, 1.0 / self.k.abs());
+    }
+}
+
+/// Draw an Apollonian gasket: a circle filled with ever smaller mutually tangent circles.
+/// https://en.wikipedia.org/wiki/Apollonian_gasket
+///
+/// plotter: device to plot to.
+/// cx_mm, cy_mm: center of the outer circle.
+/// radius_mm: radius of the outer circle.
+/// depth: number of generations of circles added to the gaps.  0 draws the outer circle and
+/// the first four circles inside it.  Each generation triples the number of circles, and
+/// circles below MIN_GASKET_RADIUS_MM are left out.
+///
+pub fn apollonian_gasket(plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64, radius_mm: f64, depth: u32) {
+    // Starting circles, relative to the center: the outer circle, two halves, and the two
+    // circles of radius 1/3 that fit above and below the halves.
+    let outer = Disc { k: -1.0 / radius_mm, kx: 0.0, ky: 0.0 };
+    let left = Disc::new(radius_mm / 2.0, -radius_mm / 2.0, 0.0);
+    let right = Disc::new(radius_mm / 2.0, radius_mm / 2.0, 0.0);
+    let top = Disc::new(radius_mm / 3.0, 0.0, 2.0 * radius_mm / 3.0);
+    let bottom = Disc::new(radius_mm / 3.0, 0.0, -2.0 * radius_mm / 3.0);
+    for disc in [outer, left, right, top, bottom].iter() {
+        disc.draw(plotter, cx_mm, cy_mm);
+    }
+
+    // Each of top and bottom leaves three gaps against the other starting circles.
+    for &new in [top, bottom].iter() {
+        fill_gap(plotter, outer, left, new, right, depth, cx_mm, cy_mm);
+        fill_gap(plotter, outer, right, new, left, depth, cx_mm, cy_mm);
+        fill_gap(plotter, left, right, new, outer, depth, cx_mm, cy_mm);
+    }
+}
+
+/// Draw the circle in the gap between a, b and c (the one that isn't d), then fill the three
+/// smaller gaps it leaves.
+fn fill_gap(plotter: &mut impl Plottable, a: Disc, b: Disc, c: Disc, d: Disc, depth: u32, cx_mm: f64, cy_mm: f64) {
+    if depth == 0 {
+        return;
+    }
+    let new = Disc::reflect(a, b, c, d);
+    if 1.0 / new.k < MIN_GASKET_RADIUS_MM {
+        return;
+    }
+    new.draw(plotter, cx_mm, cy_mm);
+    fill_gap(plotter, a, b, new, c, depth - 1, cx_mm, cy_mm);
+    fill_gap(plotter, a, c, new, b, depth - 1, cx_mm, cy_mm);
+    fill_gap(plotter, b, c, new, a, depth - 1, cx_mm, cy_mm);
+}
+
+/// Fill a rectangle with randomly placed circles that don't overlap each other or the edges.
+/// Each attempt picks a random radius and center and keeps the circle only if it fits, so
+/// the rectangle fills up quickly at first and then more slowly with small circles.
+///
+/// plotter: device to plot to.
+/// bounds: rectangle to fill, as (llx_mm, lly_mm, urx_mm, ury_mm).
+/// min_radius_mm, max_radius_mm: range of circle sizes.
+/// attempts: number of circles tried.  A few thousand gives a dense packing.
+/// seed: seed for the random placement.  The same seed gives the same plot.
+///
+pub fn circle_pack(plotter: &mut impl Plottable, bounds: (f64, f64, f64, f64), min_radius_mm: f64,
+                   max_radius_mm: f64, attempts: u32, seed: u64) {
+    // Error checking.
+    if min_radius_mm <= 0.0 || max_radius_mm < min_radius_mm {
+        panic!("Parameters must satisfy 0 < `min_radius_mm` <= `max_radius_mm`.")
+    }
+    let (llx, lly, urx, ury) = bounds;
+    let mut rng = Rng::new(seed);
+    let mut placed: Vec<(f64, f64, f64)> = Vec::new(); // (x, y, radius)
+
+    for _ in 0 .. attempts {
+        let r = rng.range(min_radius_mm, max_radius_mm);
+        if 2.0 * r > (urx - llx) || 2.0 * r > (ury - lly) {
+            continue;
+        }
+        let x = rng.range(llx + r, urx - r);
+        let y = rng.range(lly + r, ury - r);
+        let fits = placed.iter().all(|&(px, py, pr)| (x - px).powi(2) + (y - py).powi(2) >= (r + pr).powi(2));
+        if fits {
+            placed.push((x, y, r));
+            circle_at(plotter, x, y, r);
+        }
+    }
+    println!("Packed {} circles.", placed.len());
+}
+
+/// Draw a recursive fractal tree: a trunk that splits into two branches, each of which
+/// splits again, and so on.
+///
+/// plotter: device to plot to.
+/// start: base of the trunk, in mm.
+/// angle_rad: direction of the trunk (PI/2 = straight up).
+/// length_mm: length of the trunk.
+/// depth: number of levels, counting the trunk.  There are 2^depth - 1 branches in all.
+/// branch_angle_rad: angle of each branch to either side of its parent.
+/// scale: length of a branch relative to its parent, usually 0.6-0.8.
+///
+pub fn fractal_tree(plotter: &mut impl Plottable, start: (f64, f64), angle_rad: f64, length_mm: f64,
+                    depth: u32, branch_angle_rad: f64, scale: f64) {
+    branch(plotter, start, angle_rad, length_mm, depth, branch_angle_rad, scale, false);
+}
+
+/// Draw one branch of a fractal tree and everything above it.
+/// pen_here is true if the pen is already at the base, so no move is needed.
+fn branch(plotter: &mut impl Plottable, base: (f64, f64), angle_rad: f64, length_mm: f64,
+          depth: u32, branch_angle_rad: f64, scale: f64, pen_here: bool) {
+    if depth == 0 {
+        return;
+    }
+    if !pen_here {
+        plotter.move_to(base.0, base.1); // Back to the branch point.
+    }
+    let tip = (base.0 + length_mm * angle_rad.cos(), base.1 + length_mm * angle_rad.sin());
+    plotter.draw(tip.0, tip.1);
+    // The left branch continues from the tip; the right one has to come back to it.
+    branch(plotter, tip, angle_rad + branch_angle_rad, length_mm * scale, depth - 1, branch_angle_rad, scale, true);
+    branch(plotter, tip, angle_rad - branch_angle_rad, length_mm * scale, depth - 1, branch_angle_rad, scale, false);
+}
+
+/// Outline the convex hull of a set of points, e.g. to frame scattered data.
+pub fn draw_hull(plotter: &mut impl Plottable, points: &[(f64, f64)]) {
+    let hull = convex_hull(points);
+    if let Some(&(x, y)) = hull.first() {
+        plotter.move_to(x, y);
+        for &(x, y) in hull[1 ..].iter().chain(hull.first()) {
+            plotter.draw(x, y);
+        }
+    }
+}
+
+const WOBBLES: usize = 8;        // Random radius offsets around each sketchy ring, smoothly joined.
+const SKETCH_STEPS: usize = 120; // Segments in each sketchy ring.
+
+/// Draw count rings that look sketched by hand: ring k (from 1) has nominal radius
+/// base_radius_mm * k / count, so they are evenly spaced out to base_radius_mm, but each ring's
+/// radius wanders by up to jitter_mm as it goes round, and its center is nudged by up to half that.
+///
+/// plotter: device to plot to.
+/// cx_mm, cy_mm: nominal center of the rings.
+/// seed: seed for the wobbles.  The same seed gives the same plot.
+///
+/// The wobble is random offsets at WOBBLES evenly spaced angles, blended smoothly in between,
+/// so the rings stay round-ish rather than jagged, and each closes on itself.
+pub fn sketchy_rings(plotter: &mut impl Plottable, cx_mm: f64, cy_mm: f64, base_radius_mm: f64,
+                     count: u32, jitter_mm: f64, seed: u64) {
+    let mut rng = Rng::new(seed);
+    for k in 1 .. (count + 1) {
+        let radius = base_radius_mm * k as f64 / count as f64;
+        let (x0, y0) = (cx_mm + rng.range(-0.5, 0.5) * jitter_mm, cy_mm + rng.range(-0.5, 0.5) * jitter_mm);
+        let offsets: Vec<f64> = (0 .. WOBBLES).map(|_| rng.range(-jitter_mm, jitter_mm)).collect();
+        let start = rng.range(0.0, 2.0 * PI); // So the joins don't line up.
+        for i in 0 .. (SKETCH_STEPS + 1) {
+            // Cosine blend between the offsets either side of this angle.
+            let pos = WOBBLES as f64 * i as f64 / SKETCH_STEPS as f64;
+            let j = pos.floor() as usize % WOBBLES;
+            let blend = (1.0 - (PI * pos.fract()).cos()) / 2.0;
+            let r = radius + offsets[j] * (1.0 - blend) + offsets[(j + 1) % WOBBLES] * blend;
+            let angle = start + 2.0 * PI * i as f64 / SKETCH_STEPS as f64;
+            let (x, y) = (x0 + r * angle.cos(), y0 + r * angle.sin());
+            if i == 0 {
+                plotter.move_to(x, y);
+            } else {
+                plotter.draw(x, y);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockPlotter;
+
+    #[test]
+    fn square_has_four_vertices_on_the_circle() {
+        let mut mock = MockPlotter::new(-50.0, -50.0, 50.0, 50.0);
+        regular_polygon(&mut mock, 4, 10.0, 5.0, -3.0, 0.0);
+        let expected = [(15.0, -3.0), (5.0, 7.0), (-5.0, -3.0), (5.0, -13.0), (15.0, -3.0)];
+        let points = mock.points();
+        assert_eq!(points.len(), expected.len());
+        for (p, e) in points.iter().zip(expected.iter()) {
+            assert!((p.0 - e.0).hypot(p.1 - e.1) < 1e-9, "{:?} is not {:?}", p, e);
+        }
+    }
+}
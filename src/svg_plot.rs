@@ -0,0 +1,193 @@
+//! svg_plot module contains the SvgPlotter struct, which implements Plottable by writing the plot
+//! to an SVG file, for previewing and archiving plots without a plotter or a turtle window.
+//!
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::plottable::Plottable;
+
+const SVG_STROKE_MM: f64 = 0.35; // Line width in the SVG, close to a fine plotter pen.
+
+pub struct SvgPlotter {
+    path: PathBuf, // File written by finalize().
+    min_x_mm: f64, // Plot rectangle, in mm.
+    min_y_mm: f64,
+    max_x_mm: f64,
+    max_y_mm: f64,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    color: String,                 // Stroke color of the path being built.
+    data: String,                  // Path data (d attribute) being built.
+    in_subpath: bool,              // The pen is down, so a draw() continues the present subpath.
+    paths: Vec<(String, String)>,  // Finished paths, as (color, data).
+}
+
+impl SvgPlotter {
+    /// Create an SvgPlotter for the plot rectangle with lower left corner (llx_mm, lly_mm)
+    /// and upper right corner (urx_mm, ury_mm), to be written to the file at path by finalize().
+    /// The SVG's viewBox is the plot rectangle, in mm, with +y up as on the plotter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut plotter = SvgPlotter::new(Path::new("plot.svg"), -40.0, -40.0, 40.0, 40.0);
+    /// plotter.initialize();
+    /// generate_plot(&mut plotter);
+    /// plotter.finalize();
+    /// ```
+    ///
+    pub fn new(path: &Path, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> SvgPlotter {
+        // Check that the upper right is greater than the lower left.
+        if (urx_mm - llx_mm <= 0.0) || (ury_mm - lly_mm <= 0.0) {
+            panic!("Error: upper right is not greater than lower left.");
+        }
+        SvgPlotter {
+            path: path.to_path_buf(),
+            min_x_mm: llx_mm,
+            min_y_mm: lly_mm,
+            max_x_mm: urx_mm,
+            max_y_mm: ury_mm,
+            pos_x_mm: llx_mm,
+            pos_y_mm: lly_mm,
+            color: "black".to_string(),
+            data: String::new(),
+            in_subpath: false,
+            paths: Vec::new(),
+        }
+    }
+
+    /// The whole SVG document for what has been drawn so far.
+    pub fn to_svg(&self) -> String {
+        let (w, h) = (self.max_x_mm - self.min_x_mm, self.max_y_mm - self.min_y_mm);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}mm\" height=\"{}mm\" viewBox=\"{} {} {} {}\">\n",
+            w, h, self.min_x_mm, self.min_y_mm, w, h);
+        let building = (self.color.clone(), self.data.clone());
+        for (color, data) in self.paths.iter().chain(std::iter::once(&building)).filter(|p| !p.1.is_empty()) {
+            svg.push_str(&format!(
+                "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>\n",
+                data.trim_end(), color, SVG_STROKE_MM));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Append a path command for (x_mm, y_mm), flipped so +y is up in the viewBox.
+    fn push_point(&mut self, command: char, x_mm: f64, y_mm: f64) {
+        let y = self.min_y_mm + self.max_y_mm - y_mm;
+        self.data.push_str(&format!("{}{:.3} {:.3} ", command, x_mm, y));
+    }
+
+    /// Finish the path being built, if it has anything in it.
+    fn end_path(&mut self) {
+        if !self.data.is_empty() {
+            let data = std::mem::replace(&mut self.data, String::new());
+            self.paths.push((self.color.clone(), data));
+        }
+        self.in_subpath = false;
+    }
+}
+
+impl Plottable for SvgPlotter {
+
+    /// Nothing to prepare; provided for compatibility.
+    fn initialize(&mut self) {}
+
+    /// Write the SVG file.
+    fn finalize(&mut self) {
+        self.end_path();
+        if let Err(e) = fs::write(&self.path, self.to_svg()) {
+            eprintln!("Can't write {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Draw a straight line from present position to absolute position (destx_mm, desty_mm).
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        if !self.in_subpath {
+            let (x, y) = (self.pos_x_mm, self.pos_y_mm);
+            self.push_point('M', x, y);
+            self.in_subpath = true;
+        }
+        self.push_point('L', destx_mm, desty_mm);
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Move without drawing to absolute position (destx_mm, desty_mm), starting a new subpath.
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.push_point('M', destx_mm, desty_mm);
+        self.in_subpath = true;
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Draw from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Move without drawing from present position (dx, dy) mm.
+    /// Returns the new position of the pen.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Nothing to do; the next draw() continues from the present position.
+    fn pen_up(&mut self) {}
+
+    /// Make a dot: a line of no length, which shows as a round cap.
+    fn pen_down(&mut self) {
+        let (x, y) = (self.pos_x_mm, self.pos_y_mm);
+        if !self.in_subpath {
+            self.push_point('M', x, y);
+            self.in_subpath = true;
+        }
+        self.push_point('L', x, y);
+    }
+
+    /// Start a new path stroked in color_name, which should be an SVG color name.
+    fn change_color(&mut self, color_name: &str) {
+        self.end_path();
+        self.color = color_name.to_string();
+    }
+
+    /// The plot rectangle given when the plotter was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangle_is_written_as_one_path() {
+        let path = std::env::temp_dir().join("rplotter_test_triangle.svg");
+        let mut plotter = SvgPlotter::new(&path, 0.0, 0.0, 100.0, 100.0);
+        plotter.initialize();
+        plotter.change_color("red");
+        plotter.move_to(10.0, 10.0);
+        plotter.draw(90.0, 10.0);
+        plotter.draw(50.0, 80.0);
+        plotter.draw(10.0, 10.0);
+        plotter.finalize();
+        let svg = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(svg.contains("viewBox=\"0 0 100 100\""), "{}", svg);
+        // y is flipped, so y = 10 is 90 from the top.
+        assert!(svg.contains("<path d=\"M10.000 90.000 L90.000 90.000 L50.000 20.000 L10.000 90.000\" fill=\"none\" stroke=\"red\""),
+                "{}", svg);
+        assert_eq!(svg.matches("<path").count(), 1);
+    }
+}
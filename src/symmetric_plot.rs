@@ -0,0 +1,177 @@
+//! symmetric_plot module contains the SymmetricPlotter struct, a wrapper around another Plottable
+//! that draws everything twice: as given, and reflected across a mirror line.  Design one half
+//! of a bilaterally symmetric plot and the wrapper supplies the other half.
+//!
+
+use std::io;
+
+use crate::plottable::Plottable;
+
+pub struct SymmetricPlotter<'a> {
+    inner: &'a mut dyn Plottable,
+    axis_x_mm: f64, // A point on the mirror line, in mm.
+    axis_y_mm: f64,
+    cos2: f64,      // cos and sin of twice the mirror line's angle, for the reflection.
+    sin2: f64,
+    pos_x_mm: f64,  // Present position of the (unreflected) pen in mm.
+    pos_y_mm: f64,
+    path: Vec<(f64, f64)>, // Pen-down path in progress, starting at the last move.
+}
+
+impl<'a> SymmetricPlotter<'a> {
+    /// Wrap a plotter so that everything is also drawn reflected across the line through
+    /// (axis_x_mm, axis_y_mm) at angle axis_rad (0 = horizontal, PI/2 = vertical).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Left/right symmetry about the y axis.
+    /// let mut sym = SymmetricPlotter::new(&mut plotter, 0.0, 0.0, PI / 2.0);
+    /// sym.move_to(5.0, 0.0);
+    /// sym.draw(20.0, 10.0); // Also draws (-5, 0) to (-20, 10).
+    /// ```
+    ///
+    pub fn new(inner: &'a mut dyn Plottable, axis_x_mm: f64, axis_y_mm: f64, axis_rad: f64) -> SymmetricPlotter<'a> {
+        let (llx_mm, lly_mm, _, _) = inner.bounds();
+        SymmetricPlotter {
+            inner,
+            axis_x_mm,
+            axis_y_mm,
+            cos2: (2.0 * axis_rad).cos(),
+            sin2: (2.0 * axis_rad).sin(),
+            pos_x_mm: llx_mm,
+            pos_y_mm: lly_mm,
+            path: Vec::new(),
+        }
+    }
+
+    /// Reflect a point across the mirror line.
+    fn reflect(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let dx = x - self.axis_x_mm;
+        let dy = y - self.axis_y_mm;
+        (self.axis_x_mm + self.cos2 * dx + self.sin2 * dy,
+         self.axis_y_mm + self.sin2 * dx - self.cos2 * dy)
+    }
+
+    /// Draw the path in progress and its reflection.  Buffering the whole path, rather than
+    /// reflecting each segment as it arrives, keeps the pen from shuttling between the halves.
+    fn send_path(&mut self) {
+        if self.path.len() > 1 {
+            let path = std::mem::replace(&mut self.path, Vec::new());
+            for &mirrored in [false, true].iter() {
+                for (i, &p) in path.iter().enumerate() {
+                    let (x, y) = if mirrored { self.reflect(p) } else { p };
+                    if i == 0 {
+                        self.inner.move_to(x, y);
+                    } else {
+                        self.inner.draw(x, y);
+                    }
+                }
+            }
+        } else {
+            self.path.clear();
+        }
+    }
+}
+
+impl<'a> Plottable for SymmetricPlotter<'a> {
+
+    fn initialize(&mut self) {
+        self.inner.initialize();
+    }
+
+    /// Draws any path in progress, then finalizes the wrapped plotter.
+    fn finalize(&mut self) {
+        self.send_path();
+        self.inner.finalize();
+    }
+
+    /// Draw a straight line from present position to absolute position (destx_mm, desty_mm),
+    /// and its reflection.  Lines are collected until the pen is lifted.
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        if self.path.is_empty() {
+            self.path.push((self.pos_x_mm, self.pos_y_mm));
+        }
+        self.path.push((destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Move pen without drawing to absolute position (destx_mm, desty_mm).
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.send_path();
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Draw from present position (dx, dy) mm.  The reflection is drawn with the reflected offset.
+    /// Returns the new (unreflected) position of the pen.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Move the pen without drawing from present position (dx, dy) mm.
+    /// Returns the new (unreflected) position of the pen.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Draws any path in progress, then raises the pen.
+    fn pen_up(&mut self) {
+        self.send_path();
+        self.inner.pen_up();
+    }
+
+    /// Draws any path in progress, then lowers the pen at the present position and its reflection.
+    fn pen_down(&mut self) {
+        self.send_path();
+        let here = (self.pos_x_mm, self.pos_y_mm);
+        for &(x, y) in [here, self.reflect(here)].iter() {
+            self.inner.move_to(x, y);
+            self.inner.pen_down();
+        }
+    }
+
+    /// Draws any path in progress, then changes the pen.  Both halves use the new color.
+    fn change_color(&mut self, color_name: &str) {
+        self.send_path();
+        self.inner.change_color(color_name);
+    }
+
+    /// The wrapped plotter's plot rectangle.
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        self.inner.bounds()
+    }
+
+    /// Present position of the unreflected pen.
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn check_status(&self) -> io::Result<()> {
+        self.inner.check_status()
+    }
+
+    /// Draws any path in progress, then changes the speed.
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.send_path();
+        self.inner.set_velocity(cm_per_sec);
+    }
+
+    /// Draws any path in progress, then sends the pen home.
+    fn home(&mut self) {
+        self.send_path();
+        let (llx_mm, lly_mm, _, _) = self.inner.bounds();
+        self.pos_x_mm = llx_mm; // Update position.
+        self.pos_y_mm = lly_mm;
+        self.inner.home();
+    }
+
+    /// Draws any path in progress, then flushes the wrapped plotter.
+    fn flush(&mut self) {
+        self.send_path();
+        self.inner.flush();
+    }
+}
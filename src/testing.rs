@@ -0,0 +1,157 @@
+//! testing module contains the MockPlotter struct, which implements Plottable by recording every
+//! call as a PlotCommand, so plot code can be checked without a serial port or a turtle window.
+//! It is only built for tests.
+//!
+
+use crate::plottable::Plottable;
+
+/// One call made on a MockPlotter.  Relative moves are recorded with their absolute destinations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlotCommand {
+    Initialize,
+    Finalize,
+    Draw(f64, f64),
+    MoveTo(f64, f64),
+    PenUp,
+    PenDown,
+    ChangeColor(String),
+    SetVelocity(f64),
+}
+
+pub struct MockPlotter {
+    min_x_mm: f64, // Plot rectangle, in mm.
+    min_y_mm: f64,
+    max_x_mm: f64,
+    max_y_mm: f64,
+    pos_x_mm: f64, // Present position of the pen in mm.
+    pos_y_mm: f64,
+    commands: Vec<PlotCommand>, // Every call so far, in order.
+}
+
+impl MockPlotter {
+    /// Create a MockPlotter for the plot rectangle with lower left corner (llx_mm, lly_mm) and
+    /// upper right corner (urx_mm, ury_mm).  Nothing is clipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut mock = MockPlotter::new(-40.0, -40.0, 40.0, 40.0);
+    /// roulette::full_hypotrochoid(&mut mock, 30.0, 16.5, 5, 6, 0.0, 0.0, 0.0);
+    /// assert!(matches!(mock.commands()[0], PlotCommand::MoveTo(_, _)));
+    /// ```
+    ///
+    pub fn new(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> MockPlotter {
+        MockPlotter {
+            min_x_mm: llx_mm,
+            min_y_mm: lly_mm,
+            max_x_mm: urx_mm,
+            max_y_mm: ury_mm,
+            pos_x_mm: llx_mm,
+            pos_y_mm: lly_mm,
+            commands: Vec::new(),
+        }
+    }
+
+    /// The calls recorded so far, in order.
+    pub fn commands(&self) -> &[PlotCommand] {
+        &self.commands
+    }
+
+    /// Forget the calls recorded so far.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// The destinations of the MoveTo and Draw commands recorded so far, in order.
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        self.commands.iter().filter_map(|command| match command {
+            PlotCommand::MoveTo(x, y) | PlotCommand::Draw(x, y) => Some((*x, *y)),
+            _ => None,
+        }).collect()
+    }
+}
+
+impl Plottable for MockPlotter {
+
+    fn initialize(&mut self) {
+        self.commands.push(PlotCommand::Initialize);
+    }
+
+    fn finalize(&mut self) {
+        self.commands.push(PlotCommand::Finalize);
+    }
+
+    fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.commands.push(PlotCommand::Draw(destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        self.commands.push(PlotCommand::MoveTo(destx_mm, desty_mm));
+        self.pos_x_mm = destx_mm; // Update position.
+        self.pos_y_mm = desty_mm;
+    }
+
+    /// Recorded as a Draw to the new position, which is returned.
+    fn draw_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.draw(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Recorded as a MoveTo the new position, which is returned.
+    fn move_relative(&mut self, dx_mm: f64, dy_mm: f64) -> (f64, f64) {
+        self.move_to(self.pos_x_mm + dx_mm, self.pos_y_mm + dy_mm);
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    fn pen_up(&mut self) {
+        self.commands.push(PlotCommand::PenUp);
+    }
+
+    fn pen_down(&mut self) {
+        self.commands.push(PlotCommand::PenDown);
+    }
+
+    fn change_color(&mut self, color_name: &str) {
+        self.commands.push(PlotCommand::ChangeColor(color_name.to_string()));
+    }
+
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.commands.push(PlotCommand::SetVelocity(cm_per_sec));
+    }
+
+    /// The plot rectangle given when the MockPlotter was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::roulette;
+
+    #[test]
+    fn hypotrochoid_starts_with_a_move() {
+        let mut mock = MockPlotter::new(-40.0, -40.0, 40.0, 40.0);
+        roulette::full_hypotrochoid(&mut mock, 30.0, 16.5, 5, 6, 0.0, 0.0, 0.0);
+        assert_eq!(mock.commands()[0], PlotCommand::MoveTo(22.5, 0.0));
+        assert!(mock.commands()[1 ..].iter().all(|command| matches!(command, PlotCommand::Draw(..))));
+    }
+
+    #[test]
+    fn relative_moves_are_recorded_as_absolute() {
+        let mut mock = MockPlotter::new(0.0, 0.0, 100.0, 100.0);
+        mock.move_to(10.0, 10.0);
+        assert_eq!(mock.draw_relative(5.0, -2.0), (15.0, 8.0));
+        mock.change_color("red");
+        assert_eq!(mock.commands(), &[PlotCommand::MoveTo(10.0, 10.0), PlotCommand::Draw(15.0, 8.0),
+                                      PlotCommand::ChangeColor("red".to_string())]);
+    }
+}
@@ -0,0 +1,328 @@
+//! text module contains a simple single-stroke font for drawing text as pen strokes, and
+//! what is shared by the ways of drawing text.
+//!
+//! The font has upper case letters (lower case is drawn as upper case), digits and common
+//! punctuation, designed on a grid 6 units tall and (mostly) 4 units wide.  Unknown
+//! characters are drawn as '?'.
+//!
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::plottable::Plottable;
+use crate::script::ScriptError;
+
+const CAP_UNITS: f64 = 6.0;   // Font grid units from baseline to top of a capital.
+const SPACING_UNITS: f64 = 1.0; // Gap between characters.
+
+/// Where a line of text is anchored, relative to the position it is drawn at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,   // Text starts at the position.
+    Center, // Text is centered on the position.
+    Right,  // Text ends at the position.
+}
+
+impl Align {
+    /// Distance along the baseline from the anchor position back to the start of text width_mm wide.
+    pub fn offset(&self, width_mm: f64) -> f64 {
+        match self {
+            Align::Left => 0.0,
+            Align::Center => width_mm / 2.0,
+            Align::Right => width_mm,
+        }
+    }
+}
+
+// Each glyph: character, width in grid units, and strokes separated by '|',
+// each stroke a list of x,y points on the grid.
+const GLYPHS: [(char, f64, &str); 55] = [
+    (' ', 4.0, ""),
+    ('A', 4.0, "0,0 2,6 4,0|1,3 3,3"),
+    ('B', 4.0, "0,0 0,6 3,6 4,5 4,4 3,3 0,3|3,3 4,2 4,1 3,0 0,0"),
+    ('C', 4.0, "4,5 3,6 1,6 0,5 0,1 1,0 3,0 4,1"),
+    ('D', 4.0, "0,0 0,6 2,6 4,4 4,2 2,0 0,0"),
+    ('E', 4.0, "4,6 0,6 0,0 4,0|0,3 3,3"),
+    ('F', 4.0, "4,6 0,6 0,0|0,3 3,3"),
+    ('G', 4.0, "4,5 3,6 1,6 0,5 0,1 1,0 3,0 4,1 4,3 2,3"),
+    ('H', 4.0, "0,0 0,6|4,0 4,6|0,3 4,3"),
+    ('I', 2.0, "0,6 2,6|1,6 1,0|0,0 2,0"),
+    ('J', 4.0, "4,6 4,1 3,0 1,0 0,1"),
+    ('K', 4.0, "0,0 0,6|4,6 0,2|1,3 4,0"),
+    ('L', 4.0, "0,6 0,0 4,0"),
+    ('M', 4.0, "0,0 0,6 2,3 4,6 4,0"),
+    ('N', 4.0, "0,0 0,6 4,0 4,6"),
+    ('O', 4.0, "1,0 0,1 0,5 1,6 3,6 4,5 4,1 3,0 1,0"),
+    ('P', 4.0, "0,0 0,6 3,6 4,5 4,4 3,3 0,3"),
+    ('Q', 4.0, "1,0 0,1 0,5 1,6 3,6 4,5 4,1 3,0 1,0|2,2 4,0"),
+    ('R', 4.0, "0,0 0,6 3,6 4,5 4,4 3,3 0,3|2,3 4,0"),
+    ('S', 4.0, "4,5 3,6 1,6 0,5 0,4 1,3 3,3 4,2 4,1 3,0 1,0 0,1"),
+    ('T', 4.0, "0,6 4,6|2,6 2,0"),
+    ('U', 4.0, "0,6 0,1 1,0 3,0 4,1 4,6"),
+    ('V', 4.0, "0,6 2,0 4,6"),
+    ('W', 4.0, "0,6 1,0 2,3 3,0 4,6"),
+    ('X', 4.0, "0,0 4,6|0,6 4,0"),
+    ('Y', 4.0, "0,6 2,3 4,6|2,3 2,0"),
+    ('Z', 4.0, "0,6 4,6 0,0 4,0"),
+    ('0', 4.0, "1,0 0,1 0,5 1,6 3,6 4,5 4,1 3,0 1,0|0,1 4,5"),
+    ('1', 2.0, "0,5 1,6 1,0|0,0 2,0"),
+    ('2', 4.0, "0,5 1,6 3,6 4,5 4,4 0,0 4,0"),
+    ('3', 4.0, "0,5 1,6 3,6 4,5 4,4 3,3 4,2 4,1 3,0 1,0 0,1|1,3 3,3"),
+    ('4', 4.0, "3,0 3,6 0,2 4,2"),
+    ('5', 4.0, "4,6 0,6 0,3 3,3 4,2 4,1 3,0 0,0"),
+    ('6', 4.0, "4,5 3,6 1,6 0,5 0,1 1,0 3,0 4,1 4,2 3,3 0,3"),
+    ('7', 4.0, "0,6 4,6 1,0"),
+    ('8', 4.0, "1,3 0,4 0,5 1,6 3,6 4,5 4,4 3,3 1,3 0,2 0,1 1,0 3,0 4,1 4,2 3,3"),
+    ('9', 4.0, "0,1 1,0 3,0 4,1 4,5 3,6 1,6 0,5 0,4 1,3 4,3"),
+    ('.', 1.0, "0.5,0 0.5,0.5"),
+    (',', 1.0, "1,0.5 1,0 0,-1"),
+    (':', 1.0, "0.5,1 0.5,1.5|0.5,4 0.5,4.5"),
+    (';', 1.0, "0.5,4 0.5,4.5|1,1 1,0.5 0,-1"),
+    ('!', 1.0, "0.5,6 0.5,2|0.5,0 0.5,0.5"),
+    ('?', 4.0, "0,5 1,6 3,6 4,5 4,4 2,3 2,2|2,0 2,0.5"),
+    ('\'', 1.0, "0.5,6 0.5,4.5"),
+    ('"', 2.0, "0,6 0,4.5|2,6 2,4.5"),
+    ('-', 4.0, "0,3 4,3"),
+    ('+', 4.0, "0,3 4,3|2,1 2,5"),
+    ('=', 4.0, "0,2 4,2|0,4 4,4"),
+    ('*', 4.0, "2,1 2,5|0,2 4,4|0,4 4,2"),
+    ('/', 4.0, "0,0 4,6"),
+    ('(', 2.0, "2,6 1,5 1,1 2,0"),
+    (')', 2.0, "0,6 1,5 1,1 0,0"),
+    ('%', 4.0, "0,0 4,6|0,6 0,5|4,0 4,1"),
+    ('#', 4.0, "1,0 1,6|3,0 3,6|0,2 4,2|0,4 4,4"),
+    ('_', 4.0, "0,0 4,0"),
+];
+
+/// The glyph for a character: its width in grid units and its strokes.
+fn glyph(c: char) -> (f64, &'static str) {
+    let c = c.to_ascii_uppercase();
+    let found = GLYPHS.iter().find(|&&(g, _, _)| g == c)
+        .or_else(|| GLYPHS.iter().find(|&&(g, _, _)| g == '?'))
+        .unwrap();
+    (found.1, found.2)
+}
+
+/// Distance the pen moves along the baseline after drawing c, in mm.
+pub fn advance(c: char, height_mm: f64) -> f64 {
+    (glyph(c).0 + SPACING_UNITS) * height_mm / CAP_UNITS
+}
+
+/// Width of text drawn at height_mm, from the start of the first character to the end of the last.
+pub fn text_width(text: &str, height_mm: f64) -> f64 {
+    let total: f64 = text.chars().map(|c| advance(c, height_mm)).sum();
+    (total - SPACING_UNITS * height_mm / CAP_UNITS).max(0.0)
+}
+
+/// Draw one character with the left end of its baseline at (x_mm, y_mm), the baseline at angle
+/// rot_rad (0 = left to right, positive is CCW), and capitals height_mm tall.
+/// Returns the advance to the next character, in mm.
+pub fn draw_char(plotter: &mut impl Plottable, c: char, x_mm: f64, y_mm: f64, height_mm: f64, rot_rad: f64) -> f64 {
+    let unit = height_mm / CAP_UNITS;
+    let (sin, cos) = rot_rad.sin_cos();
+    for stroke in glyph(c).1.split('|').filter(|s| !s.is_empty()) {
+        for (i, point) in stroke.split(' ').enumerate() {
+            let mut coords = point.split(',').map(|v| v.parse::<f64>().unwrap() * unit);
+            let (gx, gy) = (coords.next().unwrap(), coords.next().unwrap());
+            let (px, py) = (x_mm + gx * cos - gy * sin, y_mm + gx * sin + gy * cos);
+            if i == 0 {
+                plotter.move_to(px, py);
+            } else {
+                plotter.draw(px, py);
+            }
+        }
+    }
+    advance(c, height_mm)
+}
+
+/// Draw a line of text as pen strokes.
+///
+/// plotter: device to plot to.
+/// x_mm, y_mm: anchor point on the baseline.
+/// height_mm: height of capital letters.
+/// rot_rad: direction of the baseline, 0 = left to right, positive is CCW.
+/// align: whether the text starts, is centered on, or ends at the anchor point.
+///
+/// # Examples
+///
+/// ```
+/// draw_text(&mut plotter, "HYPOTROCHOID 7/12", 0.0, -38.0, 3.0, 0.0, Align::Center);
+/// ```
+///
+pub fn draw_text(plotter: &mut impl Plottable, text: &str, x_mm: f64, y_mm: f64, height_mm: f64, rot_rad: f64, align: Align) {
+    let back = align.offset(text_width(text, height_mm));
+    let (sin, cos) = rot_rad.sin_cos();
+    let (mut x, mut y) = (x_mm - back * cos, y_mm - back * sin);
+    for c in text.chars() {
+        let step = draw_char(plotter, c, x, y, height_mm, rot_rad);
+        x += step * cos;
+        y += step * sin;
+    }
+}
+
+/// Draw text word-wrapped to fit inside bounds = (llx_mm, lly_mm, urx_mm, ury_mm), left aligned,
+/// starting from the top.  Newlines in text start a new line, and a blank line leaves a gap.
+/// Words too long for a whole line are broken between characters.
+///
+/// plotter: device to plot to.
+/// height_mm: height of capital letters.
+/// line_spacing: distance between baselines as a multiple of height_mm, e.g. 1.5.
+///
+/// Returns None if all the text fitted, or Some(index) of the byte in text where the first line
+/// that didn't fit starts, so the rest can be continued in another box.
+///
+/// # Examples
+///
+/// ```
+/// if let Some(rest) = draw_text_block(&mut plotter, caption, (-40.0, -40.0, 0.0, -20.0), 3.0, 1.5) {
+///     draw_text_block(&mut plotter, &caption[rest ..], (0.0, -40.0, 40.0, -20.0), 3.0, 1.5);
+/// }
+/// ```
+///
+pub fn draw_text_block(plotter: &mut impl Plottable, text: &str, bounds: (f64, f64, f64, f64), height_mm: f64,
+                       line_spacing: f64) -> Option<usize> {
+    let (llx, lly, urx, ury) = bounds;
+    let mut y = ury - height_mm;
+    for (start, line) in wrap_lines(text, urx - llx, height_mm) {
+        if y < lly {
+            return Some(start);
+        }
+        draw_text(plotter, &line, llx, y, height_mm, 0.0, Align::Left);
+        y -= height_mm * line_spacing;
+    }
+    None
+}
+
+/// Break text into lines no wider than width_mm, each with the byte index in text where it starts.
+fn wrap_lines(text: &str, width_mm: f64, height_mm: f64) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut offset = 0; // Of the present paragraph in text.
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut line_start = offset;
+        for (word_start, word) in words(paragraph) {
+            let word_start = offset + word_start;
+            let joined = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+            if text_width(&joined, height_mm) <= width_mm {
+                if line.is_empty() {
+                    line_start = word_start;
+                }
+                line = joined;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push((line_start, line));
+            }
+            // Start the word on a new line, breaking it up if it is still too wide.
+            line = String::new();
+            line_start = word_start;
+            for (i, c) in word.char_indices() {
+                line.push(c);
+                if text_width(&line, height_mm) > width_mm && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push((line_start, line));
+                    line = c.to_string();
+                    line_start = word_start + i;
+                }
+            }
+        }
+        lines.push((line_start, line));
+        offset += paragraph.len() + 1;
+    }
+    lines
+}
+
+/// The words of text, each with the byte index where it starts.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut found = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                found.push((s, &text[s .. i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        found.push((s, &text[s ..]));
+    }
+    found
+}
+
+/// Draw recorded pen strokes, e.g. a handwritten signature, scaled to height_mm tall with the
+/// lower left corner of their extent at (x_mm, y_mm).  Each stroke is drawn with the pen down
+/// throughout, moving with the pen up between strokes.  Coordinates are taken as y up; the units
+/// don't matter, since the strokes are scaled, and the proportions are kept.
+///
+/// # Examples
+///
+/// ```
+/// let signature = text::load_strokes(Path::new("signature.txt"))?;
+/// text::plot_strokes(&mut plotter, &signature, 20.0, -38.0, 8.0);
+/// ```
+///
+pub fn plot_strokes(plotter: &mut impl Plottable, strokes: &[Vec<(f64, f64)>], x_mm: f64, y_mm: f64, height_mm: f64) {
+    let points = || strokes.iter().flatten();
+    let min_x = points().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let min_y = points().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_y = points().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    if min_x.is_infinite() {
+        return; // Nothing to draw.
+    }
+    // A flat recording (a single horizontal line) is drawn at its own scale.
+    let scale = if max_y > min_y { height_mm / (max_y - min_y) } else { 1.0 };
+    for stroke in strokes {
+        for (i, &(sx, sy)) in stroke.iter().enumerate() {
+            let (px, py) = (x_mm + (sx - min_x) * scale, y_mm + (sy - min_y) * scale);
+            if i == 0 {
+                plotter.move_to(px, py);
+            } else {
+                plotter.draw(px, py);
+            }
+        }
+    }
+    plotter.pen_up();
+}
+
+/// Read pen strokes for plot_strokes() from a text file: one point per line as two numbers
+/// separated by a space or comma, with a blank line between strokes.  Lines starting with '#'
+/// are comments.
+pub fn load_strokes(path: &Path) -> Result<Vec<Vec<(f64, f64)>>, Box<dyn Error>> {
+    Ok(parse_strokes(&fs::read_to_string(path)?)?)
+}
+
+/// Parse the text of a strokes file.  See load_strokes().
+fn parse_strokes(recording: &str) -> Result<Vec<Vec<(f64, f64)>>, ScriptError> {
+    let mut strokes = Vec::new();
+    let mut stroke = Vec::new();
+    for (i, line) in recording.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !stroke.is_empty() {
+                strokes.push(stroke);
+                stroke = Vec::new();
+            }
+            continue;
+        }
+        let values: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|v| !v.is_empty()).collect();
+        let point = match values.as_slice() {
+            [x, y] => x.parse::<f64>().ok().zip(y.parse::<f64>().ok()),
+            _ => None,
+        };
+        match point {
+            Some(p) => stroke.push(p),
+            None => return Err(ScriptError { line: i + 1, message: format!("expected a point `x y`, found `{}`", line) }),
+        }
+    }
+    if !stroke.is_empty() {
+        strokes.push(stroke);
+    }
+    Ok(strokes)
+}
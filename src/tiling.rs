@@ -0,0 +1,243 @@
+//! The tiling module contains code to cover an area with repeated tiles.
+//! https://en.wikipedia.org/wiki/Truchet_tiles
+//!
+
+use std::f64::consts::PI;
+use crate::plottable::Plottable;
+use crate::rng::Rng;
+
+const WANG_MAX_STEPS: u32 = 1_000_000; // Tile placements tried before giving up on a Wang tiling.
+
+/// A Wang tile for wang_tiling(): a square whose edges are labeled with colors, and the strokes
+/// drawn on it.  Tiles are only placed next to each other where the shared edges have the same color,
+/// so strokes that meet an edge at the same points for the same color join up across tiles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WangTile {
+    pub north: u32, // Edge colors.
+    pub east: u32,
+    pub south: u32,
+    pub west: u32,
+    /// Polylines in tile coordinates, (0, 0) at the lower left and (1, 1) at the upper right.
+    pub strokes: Vec<Vec<(f64, f64)>>,
+}
+
+/// Fill a grid with Truchet tiles: square tiles holding two quarter circles on opposite
+/// corners, each tile randomly rotated by 90 degrees.  The arcs meet at the tile edge
+/// midpoints, so they join up into meandering, maze-like paths.
+///
+/// plotter: device to plot to.
+/// rows, cols: size of the grid in tiles.
+/// tile_mm: side length of each tile in mm.
+/// llx_mm, lly_mm: lower left corner of the grid.
+/// seed: seed for the random orientations.  The same seed gives the same plot.
+///
+pub fn truchet(plotter: &mut impl Plottable, rows: u32, cols: u32, tile_mm: f64,
+               llx_mm: f64, lly_mm: f64, seed: u64) {
+    let mut rng = Rng::new(seed);
+    let r = tile_mm / 2.0;
+
+    for row in 0 .. rows {
+        for col in 0 .. cols {
+            let x = llx_mm + col as f64 * tile_mm;
+            let y = lly_mm + row as f64 * tile_mm;
+            if rng.next_bool() {
+                // Arcs around the lower left and upper right corners.
+                plotter.ellipse_arc(x, y, r, r, 0.0, PI / 2.0);
+                plotter.ellipse_arc(x + tile_mm, y + tile_mm, r, r, PI, PI / 2.0);
+            } else {
+                // Arcs around the lower right and upper left corners.
+                plotter.ellipse_arc(x + tile_mm, y, r, r, PI / 2.0, PI / 2.0);
+                plotter.ellipse_arc(x, y + tile_mm, r, r, 1.5 * PI, PI / 2.0);
+            }
+        }
+    }
+}
+
+/// Cover bounds with a grid of cells, calling draw_cell(plotter, x_mm, y_mm) with the lower left
+/// corner of each cell.  Cells that only partly overlap the bounds are included, so the motif
+/// reaches every edge; clip it there if it must not cross.
+///
+/// plotter: device to plot to.
+/// spacing_x_mm, spacing_y_mm: size of the cells.
+/// offset_x_mm, offset_y_mm: a point the grid passes through, i.e. the corner of one cell.
+/// row_shift_mm: how far each row is shifted right of the one below it, e.g. half of
+///               spacing_x_mm for a brick or hexagonal layout, 0 for a square grid.
+/// bounds: area to cover, as (llx_mm, lly_mm, urx_mm, ury_mm).
+/// draw_cell: draws the motif for one cell.
+///
+/// # Examples
+///
+/// ```
+/// // Hypotrochoids in a hexagonal layout.
+/// tiling::tile_with_offset(plotter, 24.0, 12.0 * 3.0f64.sqrt(), 0.0, 0.0, 12.0, (-60.0, -40.0, 60.0, 40.0),
+///     |p, x, y| roulette::full_hypotrochoid(p, 5.7, 3.8, 7, 12, x + 12.0, y, 0.0));
+/// ```
+///
+pub fn tile_with_offset<P, F>(plotter: &mut P, spacing_x_mm: f64, spacing_y_mm: f64,
+                              offset_x_mm: f64, offset_y_mm: f64, row_shift_mm: f64,
+                              bounds: (f64, f64, f64, f64), mut draw_cell: F)
+    where P: Plottable, F: FnMut(&mut P, f64, f64) {
+    // Error checking.
+    if spacing_x_mm <= 0.0 || spacing_y_mm <= 0.0 {
+        panic!("Parameters `spacing_x_mm` and `spacing_y_mm` must be greater than zero.")
+    }
+
+    let (llx, lly, urx, ury) = bounds;
+    // Start from the cell containing the lower left edge, so partial cells are included.
+    let mut row = ((lly - offset_y_mm) / spacing_y_mm).floor() as i64;
+    loop {
+        let y = offset_y_mm + row as f64 * spacing_y_mm;
+        if y >= ury {
+            break;
+        }
+        let row_x_mm = offset_x_mm + row as f64 * row_shift_mm;
+        let mut col = ((llx - row_x_mm) / spacing_x_mm).floor() as i64;
+        loop {
+            let x = row_x_mm + col as f64 * spacing_x_mm;
+            if x >= urx {
+                break;
+            }
+            draw_cell(plotter, x, y);
+            col += 1;
+        }
+        row += 1;
+    }
+}
+
+/// Divide bounds into a rows × cols grid of equal cells and call draw_nth(plotter, n, cell_bounds)
+/// for each, to plot a contact sheet of variations on a design, e.g. one roulette per cell with
+/// different parameters.  Cells are numbered from 0 left to right along the top row, then the
+/// rows below, like reading a page.
+///
+/// plotter: device to plot to.
+/// rows, cols: size of the grid.
+/// cell_margin_mm: space left inside each cell edge, so neighboring designs don't touch.
+/// bounds: area to divide, as (llx_mm, lly_mm, urx_mm, ury_mm).
+/// draw_nth: draws design n within cell_bounds = (llx_mm, lly_mm, urx_mm, ury_mm), the cell less its margin.
+///
+/// # Examples
+///
+/// ```
+/// tiling::small_multiples(plotter, 3, 4, 2.0, (-60.0, -45.0, 60.0, 45.0), |p, n, (llx, lly, urx, ury)| {
+///     let radius = (urx - llx).min(ury - lly) / 2.0;
+///     roulette::full_hypotrochoid(p, radius * 0.6, radius * (0.2 + 0.05 * n as f64), 7, 12,
+///                                 (llx + urx) / 2.0, (lly + ury) / 2.0, 0.0);
+/// });
+/// ```
+///
+pub fn small_multiples<P, F>(plotter: &mut P, rows: u32, cols: u32, cell_margin_mm: f64,
+                             bounds: (f64, f64, f64, f64), draw_nth: F)
+    where P: Plottable, F: Fn(&mut P, usize, (f64, f64, f64, f64)) {
+    let (llx, lly, urx, ury) = bounds;
+    let cell_x_mm = (urx - llx) / cols as f64;
+    let cell_y_mm = (ury - lly) / rows as f64;
+
+    // Error checking.
+    if rows == 0 || cols == 0 {
+        panic!("Parameters `rows` and `cols` must be at least 1.")
+    }
+    if 2.0 * cell_margin_mm >= cell_x_mm.min(cell_y_mm) {
+        panic!("Parameter `cell_margin_mm` must leave room inside each cell.")
+    }
+
+    for row in 0 .. rows {
+        for col in 0 .. cols {
+            let x = llx + col as f64 * cell_x_mm;
+            let y = ury - (row + 1) as f64 * cell_y_mm;
+            let n = (row * cols + col) as usize;
+            draw_nth(plotter, n, (x + cell_margin_mm, y + cell_margin_mm,
+                                  x + cell_x_mm - cell_margin_mm, y + cell_y_mm - cell_margin_mm));
+        }
+    }
+}
+
+/// Fill a grid with Wang tiles, chosen at random from tile_set so that every pair of neighboring
+/// tiles has matching colors on the edge they share, and draw the strokes of each.  Tiles are
+/// placed row by row from the lower left; when no tile fits a cell, earlier choices are undone
+/// and others tried.
+///
+/// plotter: device to plot to.
+/// tile_set: the tiles to choose from.  Tiles are used as given, not rotated.
+/// cols, rows: size of the grid in tiles.
+/// tile_mm: side length of each tile in mm.
+/// llx_mm, lly_mm: lower left corner of the grid.
+/// seed: seed for the random choices.  The same seed gives the same plot.
+///
+/// Panics if the tiles can't cover the grid, or no covering is found within WANG_MAX_STEPS placements.
+///
+/// # Examples
+///
+/// ```
+/// // Color 1 edges have a line leaving their midpoint, color 0 edges don't.
+/// let tile = |n, e, s, w, strokes| WangTile { north: n, east: e, south: s, west: w, strokes };
+/// let tiles = vec![
+///     tile(1, 0, 1, 0, vec![vec![(0.5, 0.0), (0.5, 1.0)]]),
+///     tile(0, 1, 0, 1, vec![vec![(0.0, 0.5), (1.0, 0.5)]]),
+///     tile(1, 1, 0, 0, vec![vec![(0.5, 1.0), (0.5, 0.5), (1.0, 0.5)]]),
+///     tile(0, 0, 1, 1, vec![vec![(0.0, 0.5), (0.5, 0.5), (0.5, 0.0)]]),
+///     tile(0, 0, 0, 0, vec![]),
+/// ];
+/// tiling::wang_tiling(plotter, &tiles, 8, 8, 10.0, -40.0, -40.0, 7);
+/// ```
+///
+pub fn wang_tiling(plotter: &mut impl Plottable, tile_set: &[WangTile], cols: u32, rows: u32, tile_mm: f64,
+                   llx_mm: f64, lly_mm: f64, seed: u64) {
+    // Error checking.
+    if tile_set.is_empty() {
+        panic!("Parameter `tile_set` must have at least one tile.")
+    }
+    let (cols, rows) = (cols as usize, rows as usize);
+    let cells = cols * rows;
+    let mut rng = Rng::new(seed);
+
+    // Depth-first search over cells in row order.  options[i] holds the tiles still to try in
+    // cell i, in random order; the last one is the tile in use.
+    let mut options: Vec<Vec<usize>> = Vec::with_capacity(cells);
+    let mut placed: Vec<usize> = Vec::with_capacity(cells);
+    let mut steps = 0;
+    while placed.len() < cells {
+        let i = placed.len();
+        if options.len() == i {
+            // New cell: find the tiles matching the neighbors to the west and south.
+            let west = if i % cols > 0 { Some(tile_set[placed[i - 1]].east) } else { None };
+            let south = if i >= cols { Some(tile_set[placed[i - cols]].north) } else { None };
+            let mut fits: Vec<usize> = (0 .. tile_set.len())
+                .filter(|&t| west.map_or(true, |c| tile_set[t].west == c) && south.map_or(true, |c| tile_set[t].south == c))
+                .collect();
+            // Shuffle (Fisher-Yates).
+            for k in (1 .. fits.len()).rev() {
+                fits.swap(k, rng.below(k as u32 + 1) as usize);
+            }
+            options.push(fits);
+        }
+        match options[i].pop() {
+            Some(t) => placed.push(t),
+            None => {
+                // Dead end: back up to the previous cell and try its next option.
+                options.pop();
+                if placed.pop().is_none() {
+                    panic!("Parameter `tile_set` can't tile a {} x {} grid with matching edges.", cols, rows)
+                }
+            }
+        }
+        steps += 1;
+        if steps > WANG_MAX_STEPS {
+            panic!("No Wang tiling found within {} placements.", WANG_MAX_STEPS)
+        }
+    }
+
+    for (i, &t) in placed.iter().enumerate() {
+        let x = llx_mm + (i % cols) as f64 * tile_mm;
+        let y = lly_mm + (i / cols) as f64 * tile_mm;
+        for stroke in &tile_set[t].strokes {
+            for (k, &(u, v)) in stroke.iter().enumerate() {
+                if k == 0 {
+                    plotter.move_to(x + u * tile_mm, y + v * tile_mm);
+                } else {
+                    plotter.draw(x + u * tile_mm, y + v * tile_mm);
+                }
+            }
+        }
+    }
+    plotter.pen_up();
+}
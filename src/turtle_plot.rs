@@ -7,11 +7,15 @@
 // https://github.com/sunjay/turtle
 // https://turtle.rs/
 
+use std::f64::consts::PI;
+use std::path::PathBuf;
 use turtle::*;
-use crate::plottable::Plottable;
+use crate::plottable::{self, Plottable};
 
 const SCREENX_PX: i32 = 1200; // Size that fits OK on laptop main screen with menu on the left.
 const SCREENY_PX: i32 = 600;  // Could do 1280 x 640 with menu and icons on the bottom.
+const CURVE_STEP_PX: f64 = 3.0; // Length of the segments curves are drawn with on screen.
+const MAX_TURTLE_SPEED: f64 = 25.0; // Fastest animated turtle speed; faster is "instant".
 
 pub struct TurtlePlotter {
     min_x_mm: f64, // Minimum value of the pen, in mm.
@@ -23,10 +27,25 @@ pub struct TurtlePlotter {
     scale: f64,    // in mm/px.  Applies to both x and y dimensions.
     drawing: Drawing,
     turtle: Turtle,
+    export_path: Option<PathBuf>, // SVG file to save the drawing to in finalize(), if any.
 }
 
 impl TurtlePlotter {
     pub fn new(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> TurtlePlotter {
+        TurtlePlotter::new_with_export(llx_mm, lly_mm, urx_mm, ury_mm, None)
+    }
+
+    /// Create a new TurtlePlotter that also saves the drawing as an SVG file at export_path
+    /// when finalize() is called.  With None, this is the same as new().
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut plotter = TurtlePlotter::new_with_export(-40.0, -40.0, 40.0, 40.0, Some(PathBuf::from("preview.svg")));
+    /// ```
+    ///
+    pub fn new_with_export(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64, export_path: Option<PathBuf>)
+                           -> TurtlePlotter {
         // Check that the upper right is greater than the lower left.
         let size_x_mm = urx_mm - llx_mm;
         let size_y_mm = ury_mm - lly_mm;
@@ -35,24 +54,11 @@ impl TurtlePlotter {
             panic!("Error: upper right is not greater than lower left.");  // TODO: better error handling.
         }
 
-        // Figure out scaling for the window.
-        let scalex = size_x_mm / SCREENX_PX as f64;
-        let scaley = size_y_mm / SCREENY_PX as f64;
-        let scale = if scalex > scaley {
-            // In this case, the plot's x dimension in mm will span the width of the window.
-            scalex // Everything will be scaled by this factor.
-        } else {
-            // In this case, the plot's y dimension in mm will span the height of the window.
-            scaley // Everything will be scaled by this factor.
-        };
-
-        // Calculate offset to give to the turtle package.
-        let offsetx = (urx_mm + llx_mm) / 2.0 / scale;  // Center is in the average of the limits given, scaled.
-        let offsety = (ury_mm + lly_mm) / 2.0 / scale;
+        let (scale, center) = window_transform(llx_mm, lly_mm, urx_mm, ury_mm);
 
         let mut drawing = Drawing::new();  // Get the drawing first, so we can set its size and center.
         drawing.set_size([SCREENX_PX as u32, SCREENY_PX as u32]);
-        drawing.set_center([offsetx, offsety]);
+        drawing.set_center(center);
         let mut turtle = drawing.add_turtle();  // Get the turtle struct from the drawing.
         println!("drawing size: {:?}", drawing.size());
         turtle.use_radians();
@@ -68,9 +74,32 @@ impl TurtlePlotter {
             pos_y_mm: lly_mm,
             scale: scale,
             drawing: drawing,
-            turtle: turtle
+            turtle: turtle,
+            export_path,
         }
     }
+
+    /// Create a new TurtlePlotter with the plot rectangle given by its center and size,
+    /// e.g. `TurtlePlotter::new_centered(0.0, 0.0, 80.0, 80.0)` for -40..40 mm both ways.
+    pub fn new_centered(center_x_mm: f64, center_y_mm: f64, width_mm: f64, height_mm: f64) -> TurtlePlotter {
+        TurtlePlotter::new(center_x_mm - width_mm / 2.0, center_y_mm - height_mm / 2.0,
+                           center_x_mm + width_mm / 2.0, center_y_mm + height_mm / 2.0)
+    }
+}
+
+/// Scale (mm/px) and window center (in turtle units) that fit the plot rectangle in the window.
+///
+/// The turtle is sent positions in mm / scale, so one unit is one pixel.  scale is whichever of
+/// width / SCREENX_PX and height / SCREENY_PX is larger, so the plot fills the window one way and
+/// fits the other, keeping its proportions.  The center of the window is set to the center of the
+/// plot rectangle in those same units, which leaves equal margins on the two sides that don't fill.
+/// E.g. (-40, -10, 40, 10) gives 1/15 mm/px, filling the width, with center (0, 0), so y runs
+/// from -150 to 150 px in the 600 px high window.
+fn window_transform(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> (f64, [f64; 2]) {
+    let scalex = (urx_mm - llx_mm) / SCREENX_PX as f64;
+    let scaley = (ury_mm - lly_mm) / SCREENY_PX as f64;
+    let scale = scalex.max(scaley);
+    (scale, [(urx_mm + llx_mm) / 2.0 / scale, (ury_mm + lly_mm) / 2.0 / scale])
 }
 
 impl Plottable for TurtlePlotter {
@@ -81,8 +110,15 @@ impl Plottable for TurtlePlotter {
     }
 
     /// Provided for compatibility with the cutter/plotter.
+    /// Saves the drawing as SVG if an export path was given to new_with_export().
     fn finalize(&mut self) {
         self.move_to(0.0, 0.0);
+        if let Some(path) = &self.export_path {
+            match self.drawing.save_svg(path) {
+                Ok(_) => println!("Saved {}.", path.display()),
+                Err(e) => eprintln!("Can't save {}: {:?}", path.display(), e),
+            }
+        }
         println!("Finalizing.");
     }
 
@@ -121,6 +157,11 @@ impl Plottable for TurtlePlotter {
         self.turtle.pen_up();
     }
 
+    /// Lower the pen.  Nothing shows on the screen until the turtle moves.
+    fn pen_down(&mut self) {
+        self.turtle.pen_down();
+    }
+
     /// Sets the color of the pen.  Wraps the turtle command primarily so we can do something
     /// manual with the USCutter struct.
     /// See this documentation for pre-defined color string names.
@@ -130,4 +171,89 @@ impl Plottable for TurtlePlotter {
     fn change_color(&mut self, color_name: &str) {
         self.turtle.set_pen_color(color_name);
     }
-}
\ No newline at end of file
+
+    /// Animate at a turtle speed of about cm_per_sec, from 1 to 25, so slow plots can be watched
+    /// as they would draw.  The window draws instantly until this is called.
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.turtle.set_speed(cm_per_sec.round().max(1.0).min(MAX_TURTLE_SPEED) as i32);
+    }
+
+    /// Draw a whole ellipse, with segments a few pixels long at whatever size it appears on screen.
+    fn ellipse(&mut self, cx_mm: f64, cy_mm: f64, rx_mm: f64, ry_mm: f64, rot_rad: f64) {
+        let radius_px = rx_mm.abs().max(ry_mm.abs()) / self.scale;
+        let steps = ((2.0 * PI * radius_px / CURVE_STEP_PX).ceil() as i32).max(12);
+        let (x, y) = plottable::ellipse_point(cx_mm, cy_mm, rx_mm, ry_mm, rot_rad, 0.0);
+        self.move_to(x, y);
+        for i in 1 .. (steps + 1) {
+            let t = 2.0 * PI * i as f64 / steps as f64;
+            let (x, y) = plottable::ellipse_point(cx_mm, cy_mm, rx_mm, ry_mm, rot_rad, t);
+            self.draw(x, y);
+        }
+    }
+
+    /// Draw a circle around the present position, with segments about CURVE_STEP_PX long.
+    fn circle(&mut self, radius_mm: f64) {
+        self.arc(radius_mm, 360.0);
+    }
+
+    /// Draw an arc around the present position, with segments about CURVE_STEP_PX long.
+    fn arc(&mut self, radius_mm: f64, sweep_deg: f64) {
+        let (cx, cy) = (self.pos_x_mm, self.pos_y_mm);
+        let sweep = sweep_deg.to_radians();
+        let length_px = sweep.abs() * radius_mm.abs() / self.scale;
+        let steps = ((length_px / CURVE_STEP_PX).ceil() as i32).max(2);
+        self.move_to(cx + radius_mm, cy);
+        for i in 1 .. (steps + 1) {
+            let t = sweep * i as f64 / steps as f64;
+            self.draw(cx + radius_mm * t.cos(), cy + radius_mm * t.sin());
+        }
+        self.move_to(cx, cy);
+    }
+
+    /// Draw the polyline through points with the pen width scaled by weights, one per point,
+    /// interpolated along each segment.  A weight that rounds to 0 leaves a gap.
+    fn draw_weighted(&mut self, points: &[(f64, f64)], weights: &[f64]) {
+        let base_size = self.turtle.pen_size();
+        let mut last_w: Option<f64> = None;
+        for (x, y, _, _, w) in plottable::weighted_samples(points, weights) {
+            match last_w {
+                Some(last_w) if (w + last_w) / 2.0 >= 0.5 => {
+                    self.turtle.set_pen_size(base_size * (w + last_w) / 2.0);
+                    self.draw(x, y);
+                }
+                _ => self.move_to(x, y),
+            }
+            last_w = Some(w);
+        }
+        self.turtle.set_pen_size(base_size);
+        self.pen_up();
+    }
+
+    /// The plot rectangle given when the plotter was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_transform(bounds: (f64, f64, f64, f64), scale: f64, center: [f64; 2]) {
+        let (llx, lly, urx, ury) = bounds;
+        let (s, c) = window_transform(llx, lly, urx, ury);
+        assert!((s - scale).abs() < 1e-12 && (c[0] - center[0]).abs() < 1e-9 && (c[1] - center[1]).abs() < 1e-9,
+                "{:?} gave {} {:?}", bounds, s, c);
+    }
+
+    #[test]
+    fn window_transform_centers_the_plot() {
+        assert_transform((-40.0, -10.0, 40.0, 10.0), 80.0 / 1200.0, [0.0, 0.0]); // Fills the width.
+        assert_transform((0.0, 0.0, 120.0, 30.0), 0.1, [600.0, 150.0]);          // Off center, fills the width.
+        assert_transform((10.0, 0.0, 20.0, 60.0), 0.1, [150.0, 300.0]);          // Fills the height.
+    }
+}
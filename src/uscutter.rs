@@ -2,24 +2,141 @@
 //! This also makes it compatible with the turtle graphics adapter module turtle_plot.
 //!
 
-use std::io::{self, Write};
-use std::time::Duration;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use serialport; // API documentation at https://docs.rs/serialport/3.3.0/serialport/, examples at https://gitlab.com/susurrus/serialport-rs
 use serialport::DataBits::Eight;
-use serialport::FlowControl::Hardware;
+use serialport::FlowControl;
 use serialport::StopBits::One;
-use crate::plottable::Plottable;
+use crate::fill;
+use crate::hpgl::PEN_NUMBERS;
+use crate::plottable::{self, Plottable};
+use crate::text::Align;
 
 // Constants related to a USCutter LPII cutter/plotter.
 const SCALEX: f64 = 0.0251;   // mm per plotter unit. (When set at 0.025, a "150mm" line is 150.6mm long.)
 const SCALEY: f64 = 0.024917; // mm per plotter unit. (When set at 0.025, a "150mm" line is 149.5mm long.)
 const OFFSETX: i32 = 25;      // pen offset in plotter units.
 const OFFSETY: i32 = 25;      // plotter units.
+const LABEL_ASPECT: f64 = 0.7; // Character width / height for labels, close to the HPGL default.
+const MAX_TIMEOUTS: u32 = 5;  // Consecutive write timeouts before assuming the plotter has lost sync.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2); // Longest to wait for the plotter to answer a query.
+const SHARP_CORNER_DEG: f64 = 90.0; // Turns sharper than this get corner overshoot.
+const COMMAND_BUFFER_BYTES: usize = 4096; // Buffered commands are sent once there are this many bytes.
+const MIN_VELOCITY_CM_S: f64 = 1.0;  // Slowest pen speed VS accepts.
+const MAX_VELOCITY_CM_S: f64 = 38.0; // Fastest; the HP-GL range, which the LPII follows.
+const FORCE_MIN_GRAMS: i32 = 10;     // Pen force of FS step 1.
+const FORCE_STEP_GRAMS: i32 = 8;     // Added by each further step.
+const FORCE_STEPS: i32 = 8;          // Highest FS step, 66 g.
+
+/// Settings applied automatically when change_color() switches to a particular pen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenProfile {
+    pub velocity_cm_s: Option<f64>, // Pen speed (HPGL VS), or None to leave it alone.
+    pub force_grams: Option<i32>,   // Pen force, set with set_pen_force(), or None to leave it alone.
+    pub passes: u32,                // Times each line is drawn, for pens that need more ink laid down.
+}
+
+impl Default for PenProfile {
+    fn default() -> PenProfile {
+        PenProfile { velocity_cm_s: None, force_grams: None, passes: 1 }
+    }
+}
+
+/// Serial port settings for USCutter::with_config().  The default, 9600 baud with hardware flow
+/// control and a 10 ms write timeout, suits the LPII on its usual cable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SerialConfig {
+    pub baud_rate: u32,
+    pub flow_control: FlowControl, // Hardware (RTS/CTS), Software (XON/XOFF) or None.
+    pub timeout: Duration,         // How long a write may wait before it times out.
+}
+
+impl Default for SerialConfig {
+    fn default() -> SerialConfig {
+        SerialConfig { baud_rate: 9600, flow_control: FlowControl::Hardware, timeout: Duration::from_millis(10) }
+    }
+}
+
+/// Why a USCutter couldn't be created.
+#[derive(Debug)]
+pub enum USCutterError {
+    /// The upper right corner of the plot rectangle isn't above and to the right of the lower left.
+    /// dx, dy are the width and height given, in mm; at least one is not positive.
+    InvalidBounds { dx: f64, dy: f64 },
+    /// The serial port couldn't be opened, e.g. because there is no port of that name.
+    PortOpen(serialport::Error),
+    /// The output file couldn't be created.
+    Io(io::Error),
+}
+
+impl fmt::Display for USCutterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            USCutterError::InvalidBounds { dx, dy } =>
+                write!(f, "upper right is not greater than lower left (width {} mm, height {} mm)", dx, dy),
+            USCutterError::PortOpen(e) => write!(f, "can't open serial port: {}", e),
+            USCutterError::Io(e) => write!(f, "can't create plot file: {}", e),
+        }
+    }
+}
+
+impl Error for USCutterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            USCutterError::PortOpen(e) => Some(e),
+            USCutterError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serialport::Error> for USCutterError {
+    fn from(e: serialport::Error) -> USCutterError {
+        USCutterError::PortOpen(e)
+    }
+}
+
+impl From<io::Error> for USCutterError {
+    fn from(e: io::Error) -> USCutterError {
+        USCutterError::Io(e)
+    }
+}
+
+/// Where the HPGL commands go.
+enum Output {
+    Serial(Box<dyn serialport::SerialPort>), // A live plotter.
+    File(File),                              // A .plt file, for dry runs or other tools.
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Serial(port) => port.write(buf),
+            Output::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Serial(port) => port.flush(),
+            Output::File(file) => file.flush(),
+        }
+    }
+}
 
 pub struct USCutter {
     min_x_mm: f64, // Minimum value of the pen, in mm.
     min_y_mm: f64,
+    max_x_mm: f64, // Maximum value of the pen, in mm.
+    max_y_mm: f64,
     pos_x_mm: f64, // Present position of the pen in mm.
     pos_y_mm: f64,
     // Dimensions that are in plotter units 0-n, where n is an integer.
@@ -30,7 +147,42 @@ pub struct USCutter {
 //    pen_down: bool,
 //    heading_radians: f64, // Heading in radians, 0 = East, positive is CCW.
                          // (to be compatible with turtle graphics when put in standard radians mode). TODO: check this.
-    port: Box<dyn serialport::SerialPort>,
+    port: Output,
+    pen_numbers: HashMap<String, u32>, // Color name to pen number, for SP commands in plot files.
+    pen_profiles: HashMap<String, PenProfile>, // Settings to apply when changing to each color.
+    passes: u32,                   // Times each line is drawn, from the present pen's profile.
+    base_velocity_cm_s: Option<f64>, // Speed set outside any profile, restored for pens without one.  None is the plotter's default.
+    base_force_grams: Option<i32>, // Likewise for the pen force.
+    profile_velocity: bool,        // The present speed comes from a profile, not base_velocity_cm_s.
+    profile_force: bool,           // The present force comes from a profile.
+    time_budget: Option<Duration>, // Longest the plot may run, measured from initialize().
+    start_time: Option<Instant>,
+    halted: bool,                  // Set when the time budget ran out and the plot was stopped.
+    consecutive_timeouts: u32,
+    last_sent_x: i32,              // Last position the plotter accepted, in plotter units.
+    last_sent_y: i32,
+    lost_sync: bool,               // Set when the plotter stopped accepting commands; the rest of the plot is not sent.
+    min_move_mm: f64,              // Moves shorter than this are not sent.  0 sends everything.
+    sent_x_mm: f64,                // Position of the last move sent to the plotter, in mm.
+    sent_y_mm: f64,
+    pending_draw: bool,            // A short draw was held back, so the pen is not yet at pos_x_mm, pos_y_mm.
+    draft_stride: u32,             // Draw only every Nth segment of a path.  1 draws everything.
+    segment_count: u32,            // Segments drawn (or skipped) since the last move_to().
+    draft_skipped: bool,           // The last segment was skipped, so the pen is not at pos_x_mm, pos_y_mm.
+    backlash_x: i32,               // Slack taken up when the pen reverses direction, in plotter units.
+    backlash_y: i32,
+    direction_x: i32,              // Sign of the last movement along each axis, 0 before the first.
+    direction_y: i32,
+    commanded_x: i32,              // Last position requested in plotter units, before backlash compensation.
+    commanded_y: i32,
+    check_errors: bool,            // Query the error register when finalizing, and warn if it is set.
+    corner_overshoot_mm: f64,      // Distance drawn past sharp corners and back.  0 turns it off.
+    last_direction: Option<(f64, f64)>, // Unit direction of the last line drawn, None after the pen is lifted.
+    buffer: String,                // Commands not yet sent.  See flush().
+    buffer_x: i32,                 // Position the buffered commands leave the pen at, in plotter units.
+    buffer_y: i32,
+    buffer_marks: Vec<(usize, i32, i32)>, // For each buffered command, where it ends in buffer and where it leaves the pen.
+    clipped_count: u32,            // Draws and moves that reached outside the plot rectangle and were clamped.
 }
 
 impl USCutter {
@@ -43,37 +195,82 @@ impl USCutter {
     /// By default, the pen will start in the lower left corner.  You may want to move it
     /// somewhere else before starting to draw.
     ///
+    /// Returns an error if the plot rectangle is empty or the port can't be opened, so the caller
+    /// can e.g. ask for another port.
+    ///
     /// # Examples
     ///
     /// ```
-    /// let mut plotter = USCutter::new("COM12", 0.0, 0.0, 50.0, 50.0);
+    /// let mut plotter = USCutter::new("COM12", 0.0, 0.0, 50.0, 50.0)?;
     /// plotter.draw(20.0, 6.7);
     /// ```
     ///
-    pub fn new(port_name: &str, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> USCutter {
-        // Check that the upper right is greater than the lower left.
-        let size_x_mm = urx_mm - llx_mm;
-        let size_y_mm = ury_mm - lly_mm;
+    pub fn new(port_name: &str, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> Result<USCutter, USCutterError> {
+        USCutter::with_config(port_name, llx_mm, lly_mm, urx_mm, ury_mm, SerialConfig::default())
+    }
 
-        if (size_x_mm <= 0.0) || (size_y_mm <= 0.0) {
-            panic!("Error: upper right is not greater than lower left.");  // TODO: better error handling.
-        }
+    /// Create a new USCutter struct like new(), with the given serial port settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let config = SerialConfig { baud_rate: 19200, flow_control: FlowControl::Software, ..SerialConfig::default() };
+    /// let mut plotter = USCutter::with_config("COM12", 0.0, 0.0, 50.0, 50.0, config)?;
+    /// ```
+    ///
+    pub fn with_config(port_name: &str, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64, config: SerialConfig)
+                       -> Result<USCutter, USCutterError> {
+        check_bounds(llx_mm, lly_mm, urx_mm, ury_mm)?;
 
         // Get the serial port.
         let settings = serialport::SerialPortSettings {
-            baud_rate: 9600,
+            baud_rate: config.baud_rate,
             data_bits: Eight,
-            flow_control: Hardware,
+            flow_control: config.flow_control,
             parity: serialport::Parity::None,
             stop_bits: One,
-            timeout: Duration::from_millis(10)
+            timeout: config.timeout,
         };
-        let port_obj = serialport::open_with_settings(port_name, &settings).expect("can't open serial port");
+        let port_obj = serialport::open_with_settings(port_name, &settings)?;
+
+        Ok(USCutter::from_output(Output::Serial(port_obj), llx_mm, lly_mm, urx_mm, ury_mm))
+    }
+
+    /// Create a new USCutter struct with the plot rectangle given by its center and size,
+    /// e.g. `USCutter::new_centered("COM12", 0.0, 0.0, 80.0, 80.0)?` for -40..40 mm both ways.
+    pub fn new_centered(port_name: &str, center_x_mm: f64, center_y_mm: f64, width_mm: f64, height_mm: f64)
+                        -> Result<USCutter, USCutterError> {
+        USCutter::new(port_name, center_x_mm - width_mm / 2.0, center_y_mm - height_mm / 2.0,
+                      center_x_mm + width_mm / 2.0, center_y_mm + height_mm / 2.0)
+    }
+
+    /// Create a USCutter that writes its HPGL to a file instead of a plotter, for a dry run or
+    /// for other tools to use.  The file holds standard HPGL: it starts with `IN;` rather than
+    /// the LPII's wake-up sequence, and change_color() writes `SP<n>;` pen selects instead of
+    /// prompting for a pen change.
+    ///
+    /// `path`: The file to create, e.g. plot.plt.
+    /// The remaining arguments are the same as for new().
+    ///
+    /// Returns an error if the plot rectangle is empty or the file can't be created.
+    ///
+    pub fn to_file(path: &Path, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> Result<USCutter, USCutterError> {
+        check_bounds(llx_mm, lly_mm, urx_mm, ury_mm)?;
+        let file = File::create(path)?;
+        Ok(USCutter::from_output(Output::File(file), llx_mm, lly_mm, urx_mm, ury_mm))
+    }
+
+    /// Create the struct, once the output is ready.
+    fn from_output(output: Output, llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> USCutter {
+        let size_x_mm = urx_mm - llx_mm;
+        let size_y_mm = ury_mm - lly_mm;
+        let pen_numbers = PEN_NUMBERS.iter().map(|&(name, pen)| (name.to_string(), pen)).collect();
 
-        // Create the struct and return it.
         USCutter {
             min_x_mm: llx_mm,
             min_y_mm: lly_mm,
+            max_x_mm: urx_mm,
+            max_y_mm: ury_mm,
             pos_x_mm: llx_mm,
             pos_y_mm: lly_mm,
             offset_x: OFFSETX,
@@ -82,10 +279,650 @@ impl USCutter {
             max_y: (size_y_mm / SCALEY) as i32 + OFFSETY,
 //            pen_down: false,
 //            heading_radians: 0.0,
-            port: port_obj,
+            port: output,
+            pen_numbers,
+            pen_profiles: HashMap::new(),
+            passes: 1,
+            base_velocity_cm_s: None,
+            base_force_grams: None,
+            profile_velocity: false,
+            profile_force: false,
+            time_budget: None,
+            start_time: None,
+            halted: false,
+            consecutive_timeouts: 0,
+            last_sent_x: OFFSETX,
+            last_sent_y: OFFSETY,
+            lost_sync: false,
+            min_move_mm: 0.0,
+            sent_x_mm: llx_mm,
+            sent_y_mm: lly_mm,
+            pending_draw: false,
+            draft_stride: 1,
+            segment_count: 0,
+            draft_skipped: false,
+            backlash_x: 0,
+            backlash_y: 0,
+            direction_x: 0,
+            direction_y: 0,
+            commanded_x: OFFSETX,
+            commanded_y: OFFSETY,
+            check_errors: false,
+            corner_overshoot_mm: 0.0,
+            last_direction: None,
+            buffer: String::new(),
+            buffer_x: OFFSETX,
+            buffer_y: OFFSETY,
+            buffer_marks: Vec::new(),
+            clipped_count: 0,
+        }
+    }
+
+    /// Queue a command that moves the pen to (x, y) in plotter units.
+    fn send_move(&mut self, s: &str, x: i32, y: i32) {
+        self.buffer_x = x;
+        self.buffer_y = y;
+        self.queue(s);
+    }
+
+    /// Queue a command that doesn't move the pen, and send the buffer if it has filled up.
+    fn queue(&mut self, s: &str) {
+        if self.lost_sync {
+            return;
+        }
+        self.buffer.push_str(s);
+        self.buffer_marks.push((self.buffer.len(), self.buffer_x, self.buffer_y));
+        if self.buffer.len() >= COMMAND_BUFFER_BYTES {
+            self.flush();
+        }
+    }
+
+    /// Reinitialize the plotter and resend buffer, whose commands end where marks say, after the
+    /// first written bytes were accepted.  The last of those bytes may be part way through a command,
+    /// so sending restarts at the beginning of that command, with the pen re-homed to where the
+    /// command before it left off.
+    /// If any of that fails, give up: further drawing is ignored and check_connection() reports the error.
+    fn resync(&mut self, buffer: &str, marks: &[(usize, i32, i32)], written: usize) {
+        eprintln!("Plotter stopped responding after {} timeouts, reinitializing.", self.consecutive_timeouts);
+        let unsent = marks.iter().position(|&(end, _, _)| end > written).unwrap_or(marks.len());
+        let (start, x, y) = if unsent == 0 { (0, self.last_sent_x, self.last_sent_y) } else { marks[unsent - 1] };
+        let rehome = format!("PU{},{};", x, y);
+        let result = self.port.write_all(b";:H A L0 ECN U ")
+            .and_then(|_| self.port.write_all(rehome.as_bytes()))
+            .and_then(|_| self.port.write_all(buffer[start ..].as_bytes()));
+        match result {
+            Ok(_) => {
+                eprintln!("Plotter reinitialized, continuing.");
+                self.consecutive_timeouts = 0;
+                self.last_sent_x = self.buffer_x;
+                self.last_sent_y = self.buffer_y;
+            }
+            Err(e) => {
+                eprintln!("Error: could not reinitialize the plotter ({}), abandoning the plot.", e);
+                self.lost_sync = true;
+            }
+        }
+    }
+
+    /// Fill the whole plot rectangle with parallel lines spacing_mm apart at angle_rad
+    /// (0 = horizontal, positive is CCW).  Useful for testing a pen, or as a background texture.
+    pub fn fill_page_lines(&mut self, spacing_mm: f64, angle_rad: f64) {
+        let bounds = self.bounds();
+        fill::parallel_lines(self, bounds, spacing_mm, angle_rad);
+    }
+
+    /// Write text at the present pen position using the plotter's built-in font (HPGL `LB`).
+    /// This is lighter weight than drawing characters as strokes, and fine for simple annotations.
+    ///
+    /// `height_mm`: Character height (cap height) in mm.
+    /// `direction_rad`: Direction of the baseline, 0 = left to right, positive is CCW.
+    /// `align`: Whether the text starts, is centered on, or ends at the pen position.
+    ///
+    /// The label terminator (ETX, 0x03) cannot appear in a label, so it is removed, and characters
+    /// the plotter can't print (control and non-ASCII characters) are replaced by '?'.
+    /// The plotter moves the pen as it draws, so call move_to() before drawing anything else.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// plotter.move_to(5.0, 5.0);
+    /// plotter.label("Hypotrochoid 7/12", 3.0, 0.0, Align::Left);
+    /// ```
+    ///
+    pub fn label(&mut self, text: &str, height_mm: f64, direction_rad: f64, align: Align) {
+        if self.stopped() {
+            return;
+        }
+        self.flush_pending_draw();
+        let text: String = text.chars()
+            .filter(|&c| c != '\x03')
+            .map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '?' })
+            .collect();
+        if align != Align::Left {
+            let back_mm = align.offset(label_width_mm(&text, height_mm));
+            let (x_mm, y_mm) = (self.pos_x_mm - back_mm * direction_rad.cos(), self.pos_y_mm - back_mm * direction_rad.sin());
+            self.move_to(x_mm, y_mm);
+        }
+        // SI takes the character width and height in cm; DI takes the baseline direction as run, rise.
+        let s = format!("SI{:.3},{:.3};DI{:.4},{:.4};LB{}\x03",
+                        LABEL_ASPECT * height_mm / 10.0, height_mm / 10.0,
+                        direction_rad.cos(), direction_rad.sin(), text);
+        self.queue(&s);
+    }
+
+    /// Draw a dotted grid of lines every step_mm across the plot rectangle and label each
+    /// intersection with its coordinates in mm.  The lines fall on multiples of step_mm, so
+    /// the labels are round numbers.  Handy for checking where a design will land.
+    pub fn draw_coordinate_grid(&mut self, step_mm: f64) {
+        if step_mm <= 0.0 {
+            panic!("Parameter `step_mm` must be greater than zero.")
+        }
+        let (llx, lly, urx, ury) = self.bounds();
+        let xs: Vec<f64> = grid_values(llx, urx, step_mm);
+        let ys: Vec<f64> = grid_values(lly, ury, step_mm);
+
+        // Dotted lines (HPGL line type 1) so the grid stays unobtrusive.
+        self.write_setting("LT1;");
+        for &x in xs.iter() {
+            self.move_to(x, lly);
+            self.draw(x, ury);
+        }
+        for &y in ys.iter() {
+            self.move_to(llx, y);
+            self.draw(urx, y);
+        }
+        self.write_setting("LT;"); // Back to solid lines.
+
+        // Labels go above and to the right of their intersection, but labels aren't clipped, so
+        // near the top and right edges they go below or to the left instead.  Any that still
+        // don't fit are left out.
+        let height_mm = (step_mm / 8.0).min(2.5);
+        let gap_mm = height_mm / 2.0;
+        for &x in xs.iter() {
+            for &y in ys.iter() {
+                let text = format!("{},{}", x, y);
+                let width_mm = label_width_mm(&text, height_mm);
+                let (anchor_mm, align) = if x + gap_mm + width_mm <= urx {
+                    (x + gap_mm, Align::Left)
+                } else {
+                    (x - gap_mm, Align::Right)
+                };
+                let bottom_mm = if y + gap_mm + height_mm <= ury { y + gap_mm } else { y - gap_mm - height_mm };
+                if anchor_mm - align.offset(width_mm) < llx || bottom_mm < lly {
+                    continue;
+                }
+                self.move_to(anchor_mm, bottom_mm);
+                self.label(&text, height_mm, 0.0, align);
+            }
+        }
+    }
+
+    /// Queue a command that changes a plotter setting.
+    fn write_setting(&mut self, s: &str) {
+        self.queue(s);
+    }
+
+    /// True if writing to a file rather than a live plotter.
+    fn is_file(&self) -> bool {
+        match self.port {
+            Output::File(_) => true,
+            Output::Serial(_) => false,
+        }
+    }
+
+    /// Pen number for a color, assigning the next free number to colors not seen before.
+    fn pen_number(&mut self, color_name: &str) -> u32 {
+        let next = self.pen_numbers.values().max().unwrap_or(&0) + 1;
+        *self.pen_numbers.entry(color_name.to_string()).or_insert(next)
+    }
+
+    /// Limit how long the plot may run, for unattended plots.  The clock starts in initialize().
+    /// If the budget runs out, the next draw() or move_to() raises the pen and calls finalize(),
+    /// and all further drawing is ignored.  Call check_time_budget() afterwards to find out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// plotter.set_time_budget(Duration::from_secs(2 * 60 * 60));
+    /// plotter.initialize();
+    /// generate_plot(&mut plotter);
+    /// plotter.check_time_budget()?; // finalize() has already been called if this fails.
+    /// plotter.finalize();
+    /// ```
+    ///
+    pub fn set_time_budget(&mut self, dur: Duration) {
+        self.time_budget = Some(dur);
+    }
+
+    /// Returns an error if the plot was stopped because the time budget ran out.
+    pub fn check_time_budget(&self) -> io::Result<()> {
+        if self.halted {
+            Err(io::Error::new(io::ErrorKind::TimedOut,
+                               format!("plot stopped after exceeding its time budget of {:?}", self.time_budget.unwrap_or_default())))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the pen-down force with HPGL `FS`, e.g. light for fine-liners and heavier for markers.
+    /// The plotter takes a step from 1 to 8: step n is 10 + 8 (n - 1) grams, so 10, 18, 26, 34,
+    /// 42, 50, 58 and 66 g.  grams is rounded to the nearest step, and clamped to 10..66 g.
+    /// Call it after change_color() for each pen, or use set_pen_profile() to have it done there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// plotter.change_color("black");
+    /// plotter.set_pen_force(20); // FS2; (18 g)
+    /// ```
+    ///
+    pub fn set_pen_force(&mut self, grams: i32) {
+        self.base_force_grams = Some(grams);
+        self.profile_force = false;
+        self.send_pen_force(grams);
+    }
+
+    /// Send the FS command for grams, without changing the force restored after a profile.
+    fn send_pen_force(&mut self, grams: i32) {
+        let step = ((grams - FORCE_MIN_GRAMS) as f64 / FORCE_STEP_GRAMS as f64).round() as i32 + 1;
+        self.write_setting(&format!("FS{};", step.max(1).min(FORCE_STEPS)));
+    }
+
+    /// Set up the speed, force and number of passes to use with a pen.  Whenever change_color()
+    /// switches to color_name, the profile is applied, so the plot code doesn't need to repeat it.
+    /// Colors without a profile draw single passes, at the speed and force set by set_velocity() and
+    /// set_pen_force(), or the plotter's defaults if they haven't been called, so a pen that needs
+    /// no special handling isn't left with the last profile's settings.  The same goes for a profile
+    /// that leaves velocity_cm_s or force_grams as None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // The brush pen needs to go slowly, lightly and twice over.
+    /// plotter.set_pen_profile("blue", PenProfile { velocity_cm_s: Some(5.0), force_grams: Some(18), passes: 2 });
+    /// plotter.change_color("blue");
+    /// ```
+    ///
+    pub fn set_pen_profile(&mut self, color_name: &str, profile: PenProfile) {
+        self.pen_profiles.insert(color_name.to_string(), profile);
+    }
+
+    /// Apply the profile for color_name, if there is one.  Settings it doesn't give go back
+    /// to the ones from before any profile.
+    fn apply_pen_profile(&mut self, color_name: &str) {
+        let profile = self.pen_profiles.get(color_name).cloned().unwrap_or_default();
+        match profile.velocity_cm_s {
+            Some(velocity) => {
+                self.send_velocity(velocity);
+                self.profile_velocity = true;
+            }
+            None if self.profile_velocity => {
+                match self.base_velocity_cm_s {
+                    Some(velocity) => self.send_velocity(velocity),
+                    None => self.write_setting("VS;"),
+                }
+                self.profile_velocity = false;
+            }
+            None => {}
+        }
+        match profile.force_grams {
+            Some(grams) => {
+                self.send_pen_force(grams);
+                self.profile_force = true;
+            }
+            None if self.profile_force => {
+                match self.base_force_grams {
+                    Some(grams) => self.send_pen_force(grams),
+                    None => self.write_setting("FS;"),
+                }
+                self.profile_force = false;
+            }
+            None => {}
+        }
+        self.passes = profile.passes.max(1);
+    }
+
+    /// Send the VS command for cm_per_sec, without changing the speed restored after a profile.
+    fn send_velocity(&mut self, cm_per_sec: f64) {
+        let velocity = cm_per_sec.max(MIN_VELOCITY_CM_S).min(MAX_VELOCITY_CM_S);
+        self.write_setting(&format!("VS{:.0};", velocity));
+    }
+
+    /// Draw only every stride-th segment of each path, for a quick, sparse draft of a large plot
+    /// on real paper.  The pen is lifted over the skipped segments, so the drawn ones are in the
+    /// right places.  A stride of 1 (the default) restores full quality.
+    pub fn set_draft_stride(&mut self, stride: u32) {
+        self.draft_stride = stride.max(1);
+    }
+
+    /// Move the pen, raised, by (dx_mm, dy_mm) with an HPGL relative move.  Unlike move_to(), the
+    /// move isn't clipped to the plot rectangle, so the pen can be taken to a mark anywhere on the
+    /// paper, e.g. before set_origin_here().  The move is sent straight away.
+    pub fn jog(&mut self, dx_mm: f64, dy_mm: f64) {
+        if self.stopped() {
+            return;
+        }
+        self.flush_pending_draw();
+        self.last_direction = None;
+        // Whole plotter units, so the position kept here is where the pen really is.
+        let dx = (dx_mm / SCALEX).round() as i32;
+        let dy = (dy_mm / SCALEY).round() as i32;
+        let (x, y) = (self.buffer_x + dx, self.buffer_y + dy);
+        self.send_move(&format!("PU;PR{},{};PA;", dx, dy), x, y);
+        self.commanded_x = x;
+        self.commanded_y = y;
+        self.pos_x_mm += dx as f64 * SCALEX; // Update position.
+        self.pos_y_mm += dy as f64 * SCALEY;
+        self.sent_x_mm = self.pos_x_mm;
+        self.sent_y_mm = self.pos_y_mm;
+        self.flush();
+    }
+
+    /// Jog the pen from the keyboard until the user is happy with where it is, then return its
+    /// position in mm.  Each line typed is one of:
+    ///
+    /// - `l`, `r`, `u` or `d` (or several, e.g. `rru`): jog left, right, up or down by the step;
+    /// - a number: set the step in mm, 1 to start with;
+    /// - two numbers, e.g. `-12.5 3`: jog by that many mm in x and y;
+    /// - an empty line: finish.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// plotter.jog_interactive();
+    /// let bounds = plotter.set_origin_here();
+    /// ```
+    ///
+    pub fn jog_interactive(&mut self) -> (f64, f64) {
+        let mut step_mm = 1.0;
+        loop {
+            println!("Pen at ({:.2}, {:.2}) mm, step {} mm.  Type l/r/u/d to jog, a step size, dx dy, or enter when done",
+                     self.pos_x_mm, self.pos_y_mm, step_mm);
+            let mut input_line = String::new();
+            io::stdin().read_line(&mut input_line).expect("Error reading line.");
+            let input = input_line.trim();
+            if input.is_empty() {
+                break;
+            }
+            let numbers: Vec<f64> = input.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|word| !word.is_empty())
+                .filter_map(|word| word.parse().ok())
+                .collect();
+            if input.chars().all(|c| "lrud".contains(c)) {
+                for c in input.chars() {
+                    match c {
+                        'l' => self.jog(-step_mm, 0.0),
+                        'r' => self.jog(step_mm, 0.0),
+                        'u' => self.jog(0.0, step_mm),
+                        _ => self.jog(0.0, -step_mm),
+                    }
+                }
+            } else if numbers.len() == 1 && numbers[0] > 0.0 {
+                step_mm = numbers[0];
+            } else if numbers.len() == 2 {
+                self.jog(numbers[0], numbers[1]);
+            } else {
+                println!("Didn't understand {:?}", input);
+            }
+        }
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Make the present pen position the logical origin (0, 0), e.g. after jogging the pen to a
+    /// mark on the paper with jog_interactive().  The plot rectangle stays where it is on the
+    /// plotter; only its coordinates change, so a rectangle of (0, 0)-(100, 100) with the pen at
+    /// (20, 30) becomes (-20, -30)-(80, 70).  Returns the new rectangle, as bounds() does.
+    pub fn set_origin_here(&mut self) -> (f64, f64, f64, f64) {
+        self.flush_pending_draw();
+        let (dx, dy) = (self.pos_x_mm, self.pos_y_mm);
+        self.min_x_mm -= dx;
+        self.min_y_mm -= dy;
+        self.max_x_mm -= dx;
+        self.max_y_mm -= dy;
+        self.sent_x_mm -= dx;
+        self.sent_y_mm -= dy;
+        self.pos_x_mm = 0.0;
+        self.pos_y_mm = 0.0;
+        self.bounds()
+    }
+
+    /// Don't send draw() or move_to() commands that would move the pen less than threshold_mm
+    /// from where the last command left it.  Dense curves often produce runs of points that land
+    /// on the same plotter unit, and this drops the redundant commands.
+    ///
+    /// Distances are measured from the last position actually sent, not the last one requested,
+    /// so the skipped moves add up: once they total threshold_mm the pen is moved to the latest
+    /// point and the path doesn't drift.  A held-back draw is always sent before the pen is lifted,
+    /// so paths still end exactly where requested.
+    pub fn set_min_move_mm(&mut self, threshold_mm: f64) {
+        self.min_move_mm = threshold_mm.max(0.0);
+    }
+
+    /// Draw overshoot_mm past each sharp corner of a path and back before turning, so the pen lays
+    /// down a crisp, fully inked join instead of rounding the corner as it slows.  A corner is sharp
+    /// when the path turns by more than SHARP_CORNER_DEG.  0 turns overshoot off.  Applies to draw().
+    pub fn set_corner_overshoot_mm(&mut self, overshoot_mm: f64) {
+        self.corner_overshoot_mm = overshoot_mm.max(0.0);
+    }
+
+    /// If the line from the present position to (x_mm, y_mm) turns sharply from the last line
+    /// drawn, draw past the corner along the last line and back.  Remembers the new direction.
+    fn overshoot_corner(&mut self, x_mm: f64, y_mm: f64) {
+        let (dx, dy) = (x_mm - self.pos_x_mm, y_mm - self.pos_y_mm);
+        let length = dx.hypot(dy);
+        if length == 0.0 {
+            return; // A dot; keep the direction of the line before it.
+        }
+        let direction = (dx / length, dy / length);
+        if let Some((lx, ly)) = self.last_direction {
+            let turn = (lx * direction.0 + ly * direction.1).max(-1.0).min(1.0).acos();
+            if self.corner_overshoot_mm > 0.0 && turn > SHARP_CORNER_DEG.to_radians() {
+                self.flush_pending_draw();
+                let (cx, cy) = (self.pos_x_mm, self.pos_y_mm);
+                self.send_pen_move("PD", cx + lx * self.corner_overshoot_mm, cy + ly * self.corner_overshoot_mm);
+                self.send_pen_move("PD", cx, cy);
+            }
+        }
+        self.last_direction = Some(direction);
+    }
+
+    /// True if a move to (x_mm, y_mm) is too short to be worth sending.
+    fn below_min_move(&self, x_mm: f64, y_mm: f64) -> bool {
+        let dx = x_mm - self.sent_x_mm;
+        let dy = y_mm - self.sent_y_mm;
+        (dx * dx + dy * dy).sqrt() < self.min_move_mm
+    }
+
+    /// Send a draw that was held back by set_min_move_mm(), so the pen reaches the present position.
+    fn flush_pending_draw(&mut self) {
+        if self.pending_draw {
+            self.pending_draw = false;
+            let (x_mm, y_mm) = (self.pos_x_mm, self.pos_y_mm);
+            self.send_pen_move("PD", x_mm, y_mm);
+        }
+    }
+
+    /// True if a circle around (cx_mm, cy_mm) can be sent as a native HPGL curve.  Curves aren't
+    /// clipped, so the whole circle must be in the plot rectangle, and they are drawn once, so
+    /// multiple passes and draft mode need lines.
+    fn native_curve_ok(&self, cx_mm: f64, cy_mm: f64, radius_mm: f64) -> bool {
+        let r = radius_mm.abs();
+        cx_mm - r >= self.min_x_mm && cx_mm + r <= self.max_x_mm
+            && cy_mm - r >= self.min_y_mm && cy_mm + r <= self.max_y_mm
+            && self.passes == 1 && self.draft_stride == 1
+    }
+
+    /// True if a path can be sent as one multi-point PD command.  Settings that change how each
+    /// line is drawn need draw() to see every point.
+    fn native_path_ok(&self) -> bool {
+        self.passes == 1 && self.draft_stride == 1 && self.min_move_mm == 0.0
+            && self.backlash_x == 0 && self.backlash_y == 0 && self.corner_overshoot_mm == 0.0
+    }
+
+    /// With the pen down at angle start_rad on the circle around (cx_mm, cy_mm), send an AA command
+    /// that draws sweep_rad around it, and update the position to the end of the arc.
+    fn send_arc(&mut self, cx_mm: f64, cy_mm: f64, radius_mm: f64, start_rad: f64, sweep_rad: f64) {
+        let end_rad = start_rad + sweep_rad;
+        let (end_x_mm, end_y_mm) = (cx_mm + radius_mm * end_rad.cos(), cy_mm + radius_mm * end_rad.sin());
+        let center_x = self.mm2plt_x(cx_mm) + self.offset_x;
+        let center_y = self.mm2plt_y(cy_mm) + self.offset_y;
+        let end_x = self.mm2plt_x(end_x_mm) + self.offset_x;
+        let end_y = self.mm2plt_y(end_y_mm) + self.offset_y;
+        let s = format!("AA{},{},{:.2};", center_x, center_y, sweep_rad.to_degrees());
+        self.send_move(&s, end_x, end_y);
+        self.commanded_x = end_x;
+        self.commanded_y = end_y;
+        self.sent_x_mm = end_x_mm; // Update position.
+        self.sent_y_mm = end_y_mm;
+        self.pos_x_mm = end_x_mm;
+        self.pos_y_mm = end_y_mm;
+        self.last_direction = None;
+    }
+
+    /// Convert (x_mm, y_mm) to plotter units, clip, and send it with command cmd (PU or PD).
+    fn send_pen_move(&mut self, cmd: &str, x_mm: f64, y_mm: f64) {
+        let from_x = self.clip_x(self.mm2plt_x(self.sent_x_mm) + self.offset_x);
+        let from_y = self.clip_y(self.mm2plt_y(self.sent_y_mm) + self.offset_y);
+        let (x, y) = self.clip_counted(self.mm2plt_x(x_mm) + self.offset_x, self.mm2plt_y(y_mm) + self.offset_y);
+        self.sent_x_mm = x_mm;
+        self.sent_y_mm = y_mm;
+
+        self.send_compensated(cmd, x, y);
+        if cmd == "PD" {
+            // Go back over the line and forward again for each extra pass, ending at (x, y).
+            for _ in 1 .. self.passes {
+                self.send_compensated("PD", from_x, from_y);
+                self.send_compensated("PD", x, y);
+            }
         }
     }
 
+    /// Compensate for slack in the drive: when the pen reverses direction along an axis, the first
+    /// x_mm or y_mm of the move is lost taking up the slack, leaving the pen short.  With this set,
+    /// each reversal first sends a small extra move in the new direction that takes up the slack
+    /// without moving the pen, and later moves in that direction are offset to match.
+    /// Measure the slack by drawing a line out and back over itself; 0 turns compensation off.
+    /// Applies to draw() and move_to().
+    pub fn set_backlash_compensation(&mut self, x_mm: f64, y_mm: f64) {
+        self.backlash_x = (x_mm.max(0.0) / SCALEX).round() as i32;
+        self.backlash_y = (y_mm.max(0.0) / SCALEY).round() as i32;
+    }
+
+    /// Send a PU or PD command that moves the pen to (x, y) in plotter units, taking up
+    /// backlash first if the move reverses direction along either axis.
+    fn send_compensated(&mut self, cmd: &str, x: i32, y: i32) {
+        let old_comp = (self.backlash_offset_x(), self.backlash_offset_y());
+        if x != self.commanded_x {
+            self.direction_x = (x - self.commanded_x).signum();
+        }
+        if y != self.commanded_y {
+            self.direction_y = (y - self.commanded_y).signum();
+        }
+        let comp = (self.backlash_offset_x(), self.backlash_offset_y());
+        if comp != old_comp {
+            // Reversed: move by the slack in the new direction, which the pen doesn't follow.
+            let take_up_x = self.clip_x(self.commanded_x + comp.0);
+            let take_up_y = self.clip_y(self.commanded_y + comp.1);
+            self.send_move(&format!("{}{},{};", cmd, take_up_x, take_up_y), take_up_x, take_up_y);
+        }
+        self.commanded_x = x;
+        self.commanded_y = y;
+        let (x, y) = (self.clip_x(x + comp.0), self.clip_y(y + comp.1));
+        self.send_move(&format!("{}{},{};", cmd, x, y), x, y);
+    }
+
+    /// Offset added to commanded x positions: after moving in -x, the pen sits backlash_x
+    /// behind the drive, so moves are sent that much further.
+    fn backlash_offset_x(&self) -> i32 {
+        if self.direction_x < 0 { -self.backlash_x } else { 0 }
+    }
+
+    fn backlash_offset_y(&self) -> i32 {
+        if self.direction_y < 0 { -self.backlash_y } else { 0 }
+    }
+
+    /// Ask the plotter for its HPGL error register (OE) and return the code: 0 for no error,
+    /// 1 unrecognized command, 2 wrong number of parameters, 3 parameter out of range,
+    /// 5 unknown character set, 6 position overflow, 7 buffer overflow.
+    /// Reading the register clears it, so each error is reported once.
+    ///
+    /// Returns an error if writing to a file, or if the plotter doesn't answer within REPLY_TIMEOUT.
+    pub fn check_error(&mut self) -> io::Result<u8> {
+        self.flush();
+        let port = match self.port {
+            Output::Serial(ref mut port) => port,
+            Output::File(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                                          "no plotter to read errors from when writing to a file")),
+        };
+        port.write_all(b"OE;")?;
+
+        // The answer is a number ended by a carriage return.  Reads time out quickly, so keep
+        // trying until the whole line is in or REPLY_TIMEOUT has passed.
+        let start = Instant::now();
+        let mut reply = Vec::new();
+        let mut buf = [0u8; 16];
+        while !reply.contains(&b'\r') && !reply.contains(&b'\n') {
+            if start.elapsed() > REPLY_TIMEOUT {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "plotter did not answer the error query"));
+            }
+            match port.read(&mut buf) {
+                Ok(n) => reply.extend_from_slice(&buf[.. n]),
+                Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let text = String::from_utf8_lossy(&reply);
+        text.trim().parse::<u8>().map_err(|_| io::Error::new(io::ErrorKind::InvalidData,
+                                                             format!("unexpected answer to OE: {:?}", text.trim())))
+    }
+
+    /// Check the plotter's error register when finalizing, before powering it down,
+    /// and print a warning if it reports an error.  Off by default, as the check waits for an answer.
+    pub fn set_check_errors(&mut self, check: bool) {
+        self.check_errors = check;
+    }
+
+    /// Number of draws and moves so far that went outside the plot rectangle and were clamped to it.
+    /// finalize() prints a warning if there were any.
+    pub fn clip_warnings(&self) -> u32 {
+        self.clipped_count
+    }
+
+    /// Returns an error if the plot was abandoned because the plotter stopped responding
+    /// or a write to it failed.
+    pub fn check_connection(&self) -> io::Result<()> {
+        if self.lost_sync {
+            Err(io::Error::new(io::ErrorKind::TimedOut,
+                               "plotter stopped accepting commands and the rest of the plot was not sent"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns true if drawing should stop, because the time budget has run out
+    /// or the plotter has stopped responding.
+    fn stopped(&mut self) -> bool {
+        self.lost_sync || self.time_budget_expired()
+    }
+
+    /// Returns true if drawing should stop because the time budget has run out.
+    /// The first time the budget is found to be exceeded, the pen is raised and the plotter finalized.
+    fn time_budget_expired(&mut self) -> bool {
+        if self.halted {
+            return true;
+        }
+        if let (Some(budget), Some(start)) = (self.time_budget, self.start_time) {
+            if start.elapsed() > budget {
+                eprintln!("Time budget of {:?} exceeded, stopping the plot.", budget);
+                self.pen_up();
+                self.finalize();
+                self.halted = true;
+                return true;
+            }
+        }
+        false
+    }
+
     // Helper methods to manipulate dimensions.
 
     /// Convert x dimension in mm to plotter units.
@@ -98,6 +935,12 @@ impl USCutter {
         ((ymm - self.min_y_mm) / SCALEY) as i32
     }
 
+    /// Convert a radius in mm to plotter units, for CI.  The plotter draws circles in its own
+    /// units, so SCALEX is used for both directions.
+    fn mm2plt_radius(&self, radius_mm: f64) -> i32 {
+        (radius_mm.abs() / SCALEX).round() as i32
+    }
+
     /// Convert x dimension in plotter units to mm.
     fn plt2mm_x(&self, xplt: i32) -> f64 {
         xplt as f64 * SCALEX + self.min_x_mm
@@ -108,6 +951,15 @@ impl USCutter {
         yplt as f64 * SCALEY + self.min_y_mm
     }
 
+    /// Clip a position in plotter units with clip_x() and clip_y(), counting it if it was outside.
+    fn clip_counted(&mut self, x: i32, y: i32) -> (i32, i32) {
+        let (cx, cy) = (self.clip_x(x), self.clip_y(y));
+        if (cx, cy) != (x, y) {
+            self.clipped_count += 1;
+        }
+        (cx, cy)
+    }
+
     /// Clip x dimension in plotter units to [0, max].
     fn clip_x(&self, x: i32) -> i32 {
         if x < 0 {
@@ -137,8 +989,11 @@ impl Plottable for USCutter {
     /// command to the plotter to get its attention.
     /// When you are finished plotting, don't forget to call finalize().
     fn initialize(&mut self) {
-        // Prepare to plot
-        match self.port.write(b";:H A L0 ECN U ") {
+        self.start_time = Some(Instant::now());
+        // Prepare to plot.  Files get the standard HPGL initialization instead of the LPII's.
+        // These are written straight away, not buffered, so they reach the plotter before any drawing.
+        let init: &[u8] = if self.is_file() { b"IN;" } else { b";:H A L0 ECN U " };
+        match self.port.write(init) {
             Ok(_) => {
                 println!("Initializing");
             }
@@ -159,8 +1014,21 @@ impl Plottable for USCutter {
     /// Call this method to finish plotting.  It moves the pen back to the lower left corner
     /// and more importantly, turns off the various driver circuits.
     fn finalize(&mut self) {
-        // Finish plot
-        match self.port.write(b"PU0,0;!PG;") {
+        self.flush_pending_draw();
+        self.flush();
+        if self.clipped_count > 0 {
+            eprintln!("Warning: {} segments were clipped to the drawing bounds.", self.clipped_count);
+        }
+        if self.check_errors && !self.is_file() && !self.lost_sync {
+            match self.check_error() {
+                Ok(0) => {}
+                Ok(code) => eprintln!("Warning: plotter reported HPGL error {}.", code),
+                Err(e) => eprintln!("Could not read the plotter's error register: {}", e),
+            }
+        }
+        // Finish plot.  Files put the pen away instead of using the LPII's power-off command.
+        let fin: &[u8] = if self.is_file() { b"PU0,0;SP0;" } else { b"PU0,0;!PG;" };
+        match self.port.write(fin) {
             Ok(_) => {
                 println!("\nfinalizing.");
             }
@@ -175,7 +1043,7 @@ impl Plottable for USCutter {
     /// # Examples
     ///
     /// ```
-    /// let mut plotter = USCutter::new("COM12", 0.0, 0.0, 10.0, 10.0);
+    /// let mut plotter = USCutter::new("COM12", 0.0, 0.0, 10.0, 10.0)?;
     /// plotter.draw(10.0, 20.0);
     /// ```
     ///
@@ -183,40 +1051,54 @@ impl Plottable for USCutter {
     /// After that the pen will only move horizontally to (10.0, 10.0).
     ///
     fn draw(&mut self, destx_mm: f64, desty_mm: f64) {
+        if self.stopped() {
+            return;
+        }
+        // Draft mode: skip all but every draft_stride-th segment, and lift the pen over the gap.
+        let segment = self.segment_count;
+        self.segment_count += 1;
+        if segment % self.draft_stride != 0 {
+            self.flush_pending_draw();
+            self.pos_x_mm = destx_mm;
+            self.pos_y_mm = desty_mm;
+            self.draft_skipped = true;
+            self.last_direction = None;
+            return;
+        }
+        if self.draft_skipped {
+            self.draft_skipped = false;
+            let (x_mm, y_mm) = (self.pos_x_mm, self.pos_y_mm);
+            self.send_pen_move("PU", x_mm, y_mm);
+        }
+
+        self.overshoot_corner(destx_mm, desty_mm);
         self.pos_x_mm = destx_mm;
         self.pos_y_mm = desty_mm;
-        let x = self.clip_x(self.mm2plt_x(destx_mm) + self.offset_x); // Convert and clip
-        let y = self.clip_y(self.mm2plt_y(desty_mm) + self.offset_y); // Convert and clip
-
-        let s = format!("PD{},{};", x, y);
-        match self.port.write(s.as_bytes()) {
-            Ok(_) => {
-//                print!(".");
-//                std::io::stdout().flush().unwrap();
-            }
-            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => eprintln!("Timeout during operations."),
-            Err(e) => eprintln!("{:?}", e)
+        if self.below_min_move(destx_mm, desty_mm) {
+            self.pending_draw = true;
+            return;
         }
+        self.pending_draw = false;
+        self.send_pen_move("PD", destx_mm, desty_mm);
     }
 
     /// Move pen without drawing to absolute position (destx_mm, desty_mm), in units of mm.
     /// Pen movement will be clipped to within the rectangle specified when the plotter is created.
     /// See example for draw().
     fn move_to(&mut self, destx_mm: f64, desty_mm: f64) {
+        if self.stopped() {
+            return;
+        }
+        self.flush_pending_draw();
+        self.segment_count = 0;
+        self.draft_skipped = false;
+        self.last_direction = None;
         self.pos_x_mm = destx_mm;
         self.pos_y_mm = desty_mm;
-        let x = self.clip_x(self.mm2plt_x(destx_mm) + self.offset_x); // Convert and clip
-        let y = self.clip_y(self.mm2plt_y(desty_mm) + self.offset_y); // Convert and clip
-
-        let s = format!("PU{},{};", x, y);
-        match self.port.write(s.as_bytes()) {
-            Ok(_) => {
-//                print!(".");
-//                std::io::stdout().flush().unwrap();
-            }
-            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => eprintln!("Timeout during operations."),
-            Err(e) => eprintln!("{:?}", e)
+        if self.below_min_move(destx_mm, desty_mm) {
+            return;
         }
+        self.send_pen_move("PU", destx_mm, desty_mm);
     }
 
     /// Draw from present position (dx, dy) mm.
@@ -236,18 +1118,34 @@ impl Plottable for USCutter {
     /// Raise the pen.  You might want to do this when pausing motion to prevent
     /// the pen bleeding into the paper.
     fn pen_up(&mut self) {
-        match self.port.write(b"PU;") {
-            Ok(_) => {
-                print!(".");
-                std::io::stdout().flush().unwrap();
-            }
-            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => eprintln!("Timeout during operations."),
-            Err(e) => eprintln!("{:?}", e)
-        }
+        self.flush_pending_draw();
+        self.last_direction = None;
+        self.queue("PU;");
+        print!(".");
+        std::io::stdout().flush().unwrap();
+    }
 
+    /// Lower the pen where it is with a bare `PD;`, which doesn't move the carriage.
+    fn pen_down(&mut self) {
+        if self.stopped() {
+            return;
+        }
+        self.flush_pending_draw();
+        if self.draft_skipped {
+            // The pen was left at the start of a skipped segment; catch up first.
+            self.draft_skipped = false;
+            let (x_mm, y_mm) = (self.pos_x_mm, self.pos_y_mm);
+            self.send_pen_move("PU", x_mm, y_mm);
+        }
+        self.last_direction = None;
+        self.queue("PD;");
     }
 
     /// Sets the color of the pen.  Prompts the user to manually change the pen.
+    /// When writing to a file, emits an `SP<n>;` pen select instead, so the file records
+    /// which pen each part of the plot uses.  See hpgl::PEN_NUMBERS for the color to pen mapping;
+    /// other colors are given the next free pen number the first time they are used.
+    /// Then the color's PenProfile, if set, is applied.  See set_pen_profile().
     ///
     /// # Examples
     ///
@@ -257,8 +1155,385 @@ impl Plottable for USCutter {
     ///
     fn change_color(&mut self, color_name: &str) {
         self.pen_up();
-        println!("Change pens to {} and then hit enter", color_name);
-        let mut input_line = String::new();
-        io::stdin().read_line(&mut input_line).expect("Error reading line.");
+        if self.is_file() {
+            let s = format!("SP{};", self.pen_number(color_name));
+            self.queue(&s);
+        } else {
+            self.flush(); // Let the plotter finish with this pen before asking for the next.
+            println!("Change pens to {} and then hit enter", color_name);
+            let mut input_line = String::new();
+            io::stdin().read_line(&mut input_line).expect("Error reading line.");
+        }
+        self.apply_pen_profile(color_name);
+    }
+
+    /// The plot rectangle given when the plotter was created, as (llx_mm, lly_mm, urx_mm, ury_mm).
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        (self.min_x_mm, self.min_y_mm, self.max_x_mm, self.max_y_mm)
+    }
+
+    /// Present position of the pen, as (x_mm, y_mm).
+    fn position(&self) -> (f64, f64) {
+        (self.pos_x_mm, self.pos_y_mm)
+    }
+
+    /// Returns an error if the plot was stopped by the time budget (and already finalized),
+    /// or because the plotter stopped responding.  See check_time_budget() and check_connection().
+    fn check_status(&self) -> io::Result<()> {
+        self.check_time_budget()?;
+        self.check_connection()
+    }
+
+    /// Set the pen speed with HPGL `VS`.  The plotter takes whole cm/s from MIN_VELOCITY_CM_S (1) to
+    /// MAX_VELOCITY_CM_S (38); other values are clamped to that range and rounded.
+    /// Slow down for slick media, where fast lines come out ragged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// plotter.set_velocity(5.0);
+    /// ```
+    ///
+    fn set_velocity(&mut self, cm_per_sec: f64) {
+        self.base_velocity_cm_s = Some(cm_per_sec);
+        self.profile_velocity = false;
+        self.send_velocity(cm_per_sec);
+    }
+
+    /// Move with the pen up to the lower left corner of the plot rectangle and send the buffered
+    /// commands, so the carriage is parked out of the way now.  The plotter stays on, ready for more.
+    fn home(&mut self) {
+        let (x_mm, y_mm) = (self.min_x_mm, self.min_y_mm);
+        self.move_to(x_mm, y_mm);
+        self.flush();
+    }
+
+    /// Send the buffered commands to the plotter.  Drawing is buffered so that long paths go out
+    /// in a few large writes instead of one per segment; the buffer is sent whenever it reaches
+    /// COMMAND_BUFFER_BYTES, and by finalize().  Call this to make the plotter catch up sooner,
+    /// e.g. before pausing.
+    /// After MAX_TIMEOUTS timeouts in a row the plotter has probably lost sync, so it is
+    /// reinitialized, moved back to the end of the last command it accepted, and sent the rest again.
+    /// Any other write error abandons the plot, and check_connection() reports it.
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let buffer = std::mem::replace(&mut self.buffer, String::new());
+        let marks = std::mem::replace(&mut self.buffer_marks, Vec::new());
+        let mut written = 0;
+        while written < buffer.len() {
+            match self.port.write(&buffer.as_bytes()[written ..]) {
+                Ok(0) => {
+                    eprintln!("Plotter accepted no data, abandoning the plot.");
+                    self.lost_sync = true;
+                    return;
+                }
+                Ok(n) => {
+                    self.consecutive_timeouts = 0;
+                    written += n;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {
+                    eprintln!("Timeout during operations.");
+                    self.consecutive_timeouts += 1;
+                    if self.consecutive_timeouts >= MAX_TIMEOUTS {
+                        self.resync(&buffer, &marks, written);
+                        return;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: could not write to the plotter ({}), abandoning the plot.", e);
+                    self.lost_sync = true;
+                    return;
+                }
+            }
+        }
+        self.last_sent_x = self.buffer_x;
+        self.last_sent_y = self.buffer_y;
+    }
+
+    /// Draw the polyline through points with one HPGL command, `PD x1,y1,x2,y2,...;`, after moving
+    /// to the first point.  Falls back to draw() for each point when multiple passes, draft mode,
+    /// a minimum move, backlash compensation or corner overshoot is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// plotter.draw_path(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]); // PU25,25;PD423,25,423,426;
+    /// ```
+    ///
+    fn draw_path(&mut self, points: &[(f64, f64)]) {
+        let (&(x0, y0), rest) = match points.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        self.move_to(x0, y0);
+        if !self.native_path_ok() {
+            for &(x, y) in rest {
+                self.draw(x, y);
+            }
+            return;
+        }
+        if rest.is_empty() || self.stopped() {
+            return;
+        }
+        let mut s = String::from("PD");
+        let (mut x, mut y) = (self.commanded_x, self.commanded_y);
+        for (i, &(x_mm, y_mm)) in rest.iter().enumerate() {
+            let (px, py) = self.clip_counted(self.mm2plt_x(x_mm) + self.offset_x, self.mm2plt_y(y_mm) + self.offset_y);
+            if px != x {
+                self.direction_x = (px - x).signum();
+            }
+            if py != y {
+                self.direction_y = (py - y).signum();
+            }
+            if i > 0 {
+                s.push(',');
+            }
+            s.push_str(&format!("{},{}", px, py));
+            x = px;
+            y = py;
+        }
+        s.push(';');
+        self.send_move(&s, x, y);
+        let &(x_mm, y_mm) = rest.last().unwrap();
+        self.commanded_x = x;
+        self.commanded_y = y;
+        self.sent_x_mm = x_mm; // Update position.
+        self.sent_y_mm = y_mm;
+        self.pos_x_mm = x_mm;
+        self.pos_y_mm = y_mm;
+        self.segment_count += rest.len() as u32;
+        self.last_direction = None;
+    }
+
+    /// Draw a circle around the present position with one HPGL CI command.  Like sector(), falls
+    /// back to lines when the circle doesn't fit in the plot rectangle, and for multiple passes or draft mode.
+    /// The radius is converted with SCALEX, so in mm the circle is about 0.7% shorter than it is wide
+    /// (its height is SCALEY / SCALEX of its width).
+    fn circle(&mut self, radius_mm: f64) {
+        let (cx_mm, cy_mm) = (self.pos_x_mm, self.pos_y_mm);
+        if !self.native_curve_ok(cx_mm, cy_mm, radius_mm) {
+            self.ellipse_arc(cx_mm, cy_mm, radius_mm, radius_mm, 0.0, 2.0 * PI);
+            self.move_to(cx_mm, cy_mm);
+            return;
+        }
+        self.flush_pending_draw();
+        if self.stopped() {
+            return;
+        }
+        let s = format!("CI{};", self.mm2plt_radius(radius_mm));
+        let (x, y) = (self.buffer_x, self.buffer_y);
+        self.send_move(&s, x, y); // CI leaves the pen at the center.
+    }
+
+    /// Draw an arc around the present position with one HPGL AA command.  See circle().
+    fn arc(&mut self, radius_mm: f64, sweep_deg: f64) {
+        let (cx_mm, cy_mm) = (self.pos_x_mm, self.pos_y_mm);
+        if !self.native_curve_ok(cx_mm, cy_mm, radius_mm) {
+            self.ellipse_arc(cx_mm, cy_mm, radius_mm, radius_mm, 0.0, sweep_deg.to_radians());
+            self.move_to(cx_mm, cy_mm);
+            return;
+        }
+        self.move_to(cx_mm + radius_mm, cy_mm);
+        self.pen_down();
+        if self.stopped() {
+            return;
+        }
+        self.send_arc(cx_mm, cy_mm, radius_mm, 0.0, sweep_deg.to_radians());
+        self.move_to(cx_mm, cy_mm);
+    }
+
+    /// Draw a pie slice, sending the curved part as one HPGL arc (AA) instead of many short lines.
+    /// Falls back to lines like circle() does.
+    fn sector(&mut self, cx_mm: f64, cy_mm: f64, radius_mm: f64, start_rad: f64, end_rad: f64, filled: bool) {
+        if !self.native_curve_ok(cx_mm, cy_mm, radius_mm) {
+            self.move_to(cx_mm, cy_mm);
+            for &(x, y) in &plottable::sector_outline(cx_mm, cy_mm, radius_mm, start_rad, end_rad)[1 ..] {
+                self.draw(x, y);
+            }
+            self.draw(cx_mm, cy_mm);
+        } else {
+            self.move_to(cx_mm, cy_mm);
+            self.draw(cx_mm + radius_mm * start_rad.cos(), cy_mm + radius_mm * start_rad.sin());
+            self.flush_pending_draw();
+            if self.stopped() {
+                return;
+            }
+            self.send_arc(cx_mm, cy_mm, radius_mm, start_rad, end_rad - start_rad);
+            self.draw(cx_mm, cy_mm);
+        }
+        if filled {
+            plottable::fill_sector(self, cx_mm, cy_mm, radius_mm, start_rad, end_rad);
+        }
+    }
+}
+
+/// Check that the upper right is greater than the lower left.
+fn check_bounds(llx_mm: f64, lly_mm: f64, urx_mm: f64, ury_mm: f64) -> Result<(), USCutterError> {
+    let (dx, dy) = (urx_mm - llx_mm, ury_mm - lly_mm);
+    if (dx <= 0.0) || (dy <= 0.0) {
+        return Err(USCutterError::InvalidBounds { dx, dy });
+    }
+    Ok(())
+}
+
+/// Multiples of step between min and max inclusive, rounded to remove floating point noise.
+/// Length of text drawn by label() at height_mm.  Each character cell is 1.5 character widths,
+/// but the last one's spacing isn't part of the text.
+fn label_width_mm(text: &str, height_mm: f64) -> f64 {
+    (1.5 * text.chars().count() as f64 - 0.5).max(0.0) * LABEL_ASPECT * height_mm
+}
+
+fn grid_values(min: f64, max: f64, step: f64) -> Vec<f64> {
+    let first = (min / step).ceil() as i32;
+    let last = (max / step).floor() as i32;
+    (first ..= last).map(|i| (i as f64 * step * 1000.0).round() / 1000.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Run f on a USCutter writing to a file in the temporary directory, with the plot rectangle
+    /// (0, 0)-(100, 100), and return the HPGL it sent.  name keeps tests run in parallel apart.
+    fn hpgl_from(name: &str, f: impl FnOnce(&mut USCutter)) -> String {
+        let path: PathBuf = std::env::temp_dir().join(format!("rplotter_test_{}.plt", name));
+        let mut cutter = USCutter::to_file(&path, 0.0, 0.0, 100.0, 100.0).unwrap();
+        f(&mut cutter);
+        cutter.flush();
+        let hpgl = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        hpgl
+    }
+
+    #[test]
+    fn circle_radius_uses_scalex() {
+        let hpgl = hpgl_from("circle", |cutter| {
+            assert_eq!(cutter.mm2plt_radius(10.0), 398); // Not 401, as SCALEY would give.
+            assert_eq!(cutter.mm2plt_radius(-10.0), 398);
+            cutter.move_to(50.0, 50.0);
+            cutter.circle(10.0);
+        });
+        assert!(hpgl.ends_with("CI398;"), "{}", hpgl);
+    }
+
+    #[test]
+    fn draws_beyond_the_bounds_are_counted() {
+        hpgl_from("clip", |cutter| {
+            cutter.move_to(50.0, 50.0);
+            cutter.draw(60.0, 60.0);
+            assert_eq!(cutter.clip_warnings(), 0);
+            cutter.draw(150.0, 60.0); // Past max_x.
+            assert_eq!(cutter.clip_warnings(), 1);
+            cutter.move_to(50.0, -10.0);
+            cutter.draw(50.0, 50.0);
+            assert_eq!(cutter.clip_warnings(), 2);
+        });
+    }
+
+    #[test]
+    fn grid_labels_stay_in_the_plot_rectangle() {
+        let mut max = (0, 0);
+        let hpgl = hpgl_from("grid", |cutter| {
+            max = (cutter.max_x, cutter.max_y);
+            cutter.draw_coordinate_grid(20.0);
+            assert_eq!(cutter.clip_warnings(), 0);
+        });
+        // Each label is drawn from where the last PU left the pen.
+        let mut pen = (0, 0);
+        let mut labels = 0;
+        for command in hpgl.split(';') {
+            let command = match command.find("LB") {
+                Some(start) => {
+                    let label = &command[start + 2 ..];
+                    let end = label.find('\x03').unwrap();
+                    let text = &label[.. end];
+                    let width = (label_width_mm(text, 2.5) / SCALEX).ceil() as i32;
+                    let height = (2.5 / SCALEY).ceil() as i32;
+                    assert!(pen.0 + width <= max.0 && pen.1 + height <= max.1, "{} at {:?}", text, pen);
+                    labels += 1;
+                    &label[end + 1 ..]
+                }
+                None => command,
+            };
+            if let Some(coords) = command.strip_prefix("PU") {
+                let coords: Vec<i32> = coords.split(',').map(|n| n.parse().unwrap()).collect();
+                pen = (coords[0], coords[1]);
+            }
+        }
+        assert_eq!(labels, 36); // 0, 20, ... 100 both ways.
+    }
+
+    #[test]
+    fn jog_leaves_the_rectangle_and_sets_the_origin() {
+        let hpgl = hpgl_from("jog", |cutter| {
+            cutter.move_to(10.0, 10.0);
+            cutter.jog(-30.0, 5.0); // 20 mm left of the rectangle.
+            assert_eq!(cutter.clip_warnings(), 0);
+            let (x, y) = cutter.position();
+            assert!((x - (10.0 - 1195.0 * SCALEX)).abs() < 1e-9 && (y - (10.0 + 201.0 * SCALEY)).abs() < 1e-9);
+
+            let (llx, lly, urx, ury) = cutter.set_origin_here();
+            assert_eq!((llx, lly, urx, ury), (-x, -y, 100.0 - x, 100.0 - y));
+            assert_eq!(cutter.position(), (0.0, 0.0));
+        });
+        assert_eq!(hpgl, "PU423,426;PU;PR-1195,201;PA;");
+    }
+
+    #[test]
+    fn path_is_sent_as_one_pd_command() {
+        let hpgl = hpgl_from("path", |cutter| {
+            cutter.draw_path(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        });
+        assert_eq!(hpgl, "PU25,25;PD423,25,423,426;");
+    }
+
+    #[test]
+    fn serial_config_defaults_and_overrides() {
+        let config = SerialConfig::default();
+        assert_eq!(config.baud_rate, 9600);
+        assert_eq!(config.flow_control, FlowControl::Hardware);
+        assert_eq!(config.timeout, Duration::from_millis(10));
+
+        let config = SerialConfig { baud_rate: 19200, flow_control: FlowControl::Software, ..SerialConfig::default() };
+        assert_eq!((config.baud_rate, config.flow_control, config.timeout),
+                   (19200, FlowControl::Software, Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn pen_force_is_sent_as_the_nearest_step() {
+        let hpgl = hpgl_from("force", |cutter| {
+            cutter.set_pen_force(10);  // FS1
+            cutter.set_pen_force(20);  // 18 g is nearest: FS2
+            cutter.set_pen_force(66);  // FS8
+            cutter.set_pen_force(100); // Clamped to FS8
+            cutter.set_pen_force(0);   // Clamped to FS1
+        });
+        assert_eq!(hpgl, "FS1;FS2;FS8;FS8;FS1;");
+    }
+
+    #[test]
+    fn pen_profile_force_is_in_grams() {
+        let hpgl = hpgl_from("profile", |cutter| {
+            cutter.set_pen_profile("blue", PenProfile { force_grams: Some(26), ..PenProfile::default() });
+            cutter.change_color("blue");
+        });
+        assert!(hpgl.ends_with("FS3;"), "{}", hpgl);
+    }
+
+    #[test]
+    fn pen_without_a_profile_gets_the_settings_back() {
+        let hpgl = hpgl_from("restore", |cutter| {
+            cutter.set_velocity(20.0);
+            cutter.set_pen_profile("blue", PenProfile { velocity_cm_s: Some(5.0), force_grams: Some(18), passes: 1 });
+            cutter.change_color("blue");
+            cutter.change_color("black");
+            cutter.change_color("red");
+        });
+        // Black goes back to 20 cm/s and the plotter's default force; red, already there, needs nothing.
+        assert_eq!(hpgl, "VS20;PU;SP4;VS5;FS2;PU;SP1;VS20;FS;PU;SP2;");
     }
 }
@@ -0,0 +1,167 @@
+//! The voronoi module contains code to draw Voronoi diagrams: the boundaries between regions
+//! of the plane closest to each of a set of seed points, and the polygon skeleton built on them.
+//! https://en.wikipedia.org/wiki/Voronoi_diagram
+//!
+
+use std::f64::consts::PI;
+
+use crate::geometry::point_in_polygon;
+use crate::plottable::Plottable;
+
+const SKELETON_SAMPLES: usize = 400;             // Points the polygon boundary is sampled at for skeleton().
+const SKELETON_MIN_ANGLE_RAD: f64 = PI / 3.0;    // Smallest angle between the samples either side of a skeleton edge.
+
+/// Which line an edge of a cell lies on.
+#[derive(Clone, Copy, PartialEq)]
+enum Edge {
+    Border,      // The edge of the bounds.
+    Seed(usize), // The perpendicular bisector with this seed.
+}
+
+/// Draw the Voronoi diagram of the seed points, clipped to bounds = (llx_mm, lly_mm, urx_mm, ury_mm).
+/// Each boundary between two cells is drawn once, and the bounds rectangle is drawn as the outer edge.
+///
+/// The time grows with the square of the number of seeds; a few thousand is fine.
+pub fn voronoi_edges(plotter: &mut impl Plottable, seeds: &[(f64, f64)], bounds: (f64, f64, f64, f64)) {
+    let (llx, lly, urx, ury) = bounds;
+    for (i, j, a, b) in shared_edges(seeds, bounds) {
+        if j > i {
+            plotter.move_to(a.0, a.1);
+            plotter.draw(b.0, b.1);
+        }
+    }
+    // The outer edge.
+    plotter.move_to(llx, lly);
+    plotter.draw(urx, lly);
+    plotter.draw(urx, ury);
+    plotter.draw(llx, ury);
+    plotter.draw(llx, lly);
+}
+
+/// Draw an approximate medial axis (skeleton) of a polygon: the points inside it that are
+/// equally close to two different parts of its boundary.
+/// https://en.wikipedia.org/wiki/Medial_axis
+///
+/// The boundary is sampled at SKELETON_SAMPLES points, and the skeleton is made of the edges of
+/// their Voronoi diagram that lie inside the polygon and separate samples on opposite sides of it,
+/// i.e. samples that subtend at least SKELETON_MIN_ANGLE_RAD at both ends of the edge.  That drops
+/// the short edges between neighboring samples, and the branches into corners flatter than 120°,
+/// which would otherwise clutter polygons that approximate curves.
+///
+/// plotter: device to plot to.
+/// polygon: vertices of a simple polygon in order; the last vertex joins back to the first.
+///
+pub fn skeleton(plotter: &mut impl Plottable, polygon: &[(f64, f64)]) {
+    if polygon.len() < 3 {
+        return;
+    }
+    let n = polygon.len();
+    let perimeter: f64 = (0 .. n).map(|k| distance(polygon[k], polygon[(k + 1) % n])).sum();
+    let step = perimeter / SKELETON_SAMPLES as f64;
+    let mut samples = Vec::new();
+    for k in 0 .. n {
+        let (p, q) = (polygon[k], polygon[(k + 1) % n]);
+        let pieces = ((distance(p, q) / step).ceil() as usize).max(1);
+        for m in 0 .. pieces {
+            let t = m as f64 / pieces as f64;
+            samples.push((p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1)));
+        }
+    }
+
+    // Voronoi bounds: the polygon's bounding box with some room around it.
+    let (mut llx, mut lly, mut urx, mut ury) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for &(x, y) in polygon {
+        llx = llx.min(x);
+        lly = lly.min(y);
+        urx = urx.max(x);
+        ury = ury.max(y);
+    }
+    let margin = (urx - llx).max(ury - lly);
+    let bounds = (llx - margin, lly - margin, urx + margin, ury + margin);
+
+    let opposite = |m: (f64, f64), si: (f64, f64), sj: (f64, f64)| {
+        let (u, v) = ((si.0 - m.0, si.1 - m.1), (sj.0 - m.0, sj.1 - m.1));
+        (u.0 * v.1 - u.1 * v.0).abs().atan2(u.0 * v.0 + u.1 * v.1) >= SKELETON_MIN_ANGLE_RAD
+    };
+    let mut segments: Vec<((f64, f64), (f64, f64))> = shared_edges(&samples, bounds).into_iter()
+        .filter(|&(i, j, a, b)| i < j && point_in_polygon(a, polygon) && point_in_polygon(b, polygon)
+                && opposite(a, samples[i], samples[j]) && opposite(b, samples[i], samples[j]))
+        .map(|(_, _, a, b)| (a, b))
+        .collect();
+
+    // Chain the edges into paths, so the pen is not lifted between every one.
+    let joins = |p: (f64, f64), q: (f64, f64)| distance(p, q) < 1e-6;
+    while let Some((a, b)) = segments.pop() {
+        plotter.move_to(a.0, a.1);
+        plotter.draw(b.0, b.1);
+        let mut end = b;
+        while let Some(k) = segments.iter().position(|&(p, q)| joins(p, end) || joins(q, end)) {
+            let (p, q) = segments.swap_remove(k);
+            end = if joins(p, end) { q } else { p };
+            plotter.draw(end.0, end.1);
+        }
+    }
+}
+
+fn distance(p: (f64, f64), q: (f64, f64)) -> f64 {
+    (q.0 - p.0).hypot(q.1 - p.1)
+}
+
+/// Every edge between two Voronoi cells within bounds, as (i, j, a, b): the edge from a to b
+/// separates the cells of seeds i and j.  Each edge is found from both sides, once with i < j
+/// and once with i > j.
+///
+/// Each cell is found by cutting the bounds rectangle down with the bisector between its seed and
+/// every other seed.
+fn shared_edges(seeds: &[(f64, f64)], bounds: (f64, f64, f64, f64)) -> Vec<(usize, usize, (f64, f64), (f64, f64))> {
+    let (llx, lly, urx, ury) = bounds;
+    let mut edges = Vec::new();
+    for (i, &si) in seeds.iter().enumerate() {
+        // Start with the whole rectangle.  Each vertex carries the edge running from it to the next vertex.
+        let mut cell = vec![((llx, lly), Edge::Border), ((urx, lly), Edge::Border),
+                            ((urx, ury), Edge::Border), ((llx, ury), Edge::Border)];
+        for (j, &sj) in seeds.iter().enumerate() {
+            if j == i || sj == si {
+                continue;
+            }
+            // Points closer to si than to sj satisfy n . p <= c.
+            let n = (sj.0 - si.0, sj.1 - si.1);
+            let c = (sj.0 * sj.0 + sj.1 * sj.1 - si.0 * si.0 - si.1 * si.1) / 2.0;
+            cell = clip_cell(&cell, n, c, Edge::Seed(j));
+            if cell.is_empty() {
+                break;
+            }
+        }
+        for k in 0 .. cell.len() {
+            if let Edge::Seed(j) = cell[k].1 {
+                edges.push((i, j, cell[k].0, cell[(k + 1) % cell.len()].0));
+            }
+        }
+    }
+    edges
+}
+
+/// Cut a convex cell down to the half-plane n . p <= c (Sutherland-Hodgman).
+/// The new edge along the cut is tagged `cut`.
+fn clip_cell(cell: &[((f64, f64), Edge)], n: (f64, f64), c: f64, cut: Edge) -> Vec<((f64, f64), Edge)> {
+    let inside = |p: (f64, f64)| n.0 * p.0 + n.1 * p.1 <= c;
+    let mut out = Vec::with_capacity(cell.len() + 1);
+    for k in 0 .. cell.len() {
+        let (p, edge) = cell[k];
+        let q = cell[(k + 1) % cell.len()].0;
+        let crossing = || {
+            let t = (c - n.0 * p.0 - n.1 * p.1) / (n.0 * (q.0 - p.0) + n.1 * (q.1 - p.1));
+            (p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1))
+        };
+        match (inside(p), inside(q)) {
+            (true, true) => out.push((p, edge)),
+            (true, false) => {
+                out.push((p, edge));
+                out.push((crossing(), cut)); // Leaving: the cut runs from here to where the cell comes back in.
+            }
+            (false, true) => out.push((crossing(), edge)),
+            (false, false) => {}
+        }
+    }
+    out
+}